@@ -0,0 +1,89 @@
+use std::io;
+
+use argh::FromArgs;
+
+#[derive(FromArgs)]
+#[argh(
+    description = "serve process memory to dumper on another machine. the protocol has no authentication, anyone who \
+                   can connect can read and write the memory of every process the agent can access"
+)]
+struct Args {
+    #[argh(
+        option,
+        default = "String::from(\"127.0.0.1:7600\")",
+        description = "listen address, host:port or unix:/path. default 127.0.0.1:7600"
+    )]
+    listen: String,
+    #[argh(
+        option,
+        default = "false",
+        description = "allow listening on a non-loopback address, only use it on a trusted network. default false"
+    )]
+    allow_remote: bool,
+}
+
+fn main() {
+    let Args { listen, allow_remote } = argh::from_env();
+    if let Err(err) = run(&listen, allow_remote) {
+        eprintln!("\n\x1b[31m error: {err} \x1b[0m")
+    }
+}
+
+// 每个连接使用一个线程，连接中的进程由客户端指定
+// 协议没有认证，默认只允许监听回环地址，unix socket 由文件权限控制
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn run(listen: &str, allow_remote: bool) -> Result<(), io::Error> {
+    use std::{
+        fs::{self, Permissions},
+        net::TcpListener,
+        os::unix::{fs::PermissionsExt, net::UnixListener},
+        thread,
+    };
+
+    use vmmap::linux::remote::serve;
+
+    match listen.strip_prefix("unix:") {
+        Some(path) => {
+            let listener = UnixListener::bind(path)?;
+            // 创建时的权限取决于 umask，只允许当前用户连接
+            fs::set_permissions(path, Permissions::from_mode(0o600))?;
+            println!("listening on {listen}");
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => drop(thread::spawn(move || report(serve(stream)))),
+                    Err(err) => report(Err(err)),
+                }
+            }
+        }
+        None => {
+            let listener = TcpListener::bind(listen)?;
+            let addr = listener.local_addr()?;
+            if !addr.ip().is_loopback() && !allow_remote {
+                return Err(io::Error::other(format!(
+                    "refusing to listen on {addr}, anyone who can connect can access process memory. use \
+                     --allow-remote true on a trusted network"
+                )));
+            }
+            println!("listening on {addr}");
+            for stream in listener.incoming() {
+                match stream.and_then(|s| s.set_nodelay(true).map(|_| s)) {
+                    Ok(stream) => drop(thread::spawn(move || report(serve(stream)))),
+                    Err(err) => report(Err(err)),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn run(_: &str, _: bool) -> Result<(), io::Error> {
+    Err(io::Error::other("the agent is only supported on linux"))
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn report(result: Result<(), io::Error>) {
+    if let Err(err) = result {
+        eprintln!("connection error: {err}")
+    }
+}
@@ -1,94 +1,128 @@
-use std::{fmt::Write, mem, path::Path};
+use std::fmt::Write;
 
+use ptrsx::{ChainResolver, Layout, ModuleBase, PointerChain, ResolveStep};
 #[cfg(target_os = "macos")]
 use vmmap::macos::cmd::ProcessInfoCmdFixed as ProcessInfo;
-#[cfg(any(target_os = "linux", target_os = "windows", target_os = "android"))]
+#[cfg(not(target_os = "macos"))]
 use vmmap::ProcessInfo;
-use vmmap::{Process, VirtualMemoryRead, VirtualMemoryWrite, VirtualQuery};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use vmmap::{CoreDump, Minidump, RawImage, RemoteProcess};
+use vmmap::{Process, VirtualMemoryRead, VirtualMemoryWrite};
 
-use super::{Error, TestChainCommand};
+use super::{module_base, pointer_layout, source, Error, Source, TestChainCommand};
 
 impl TestChainCommand {
     pub fn init(self) -> Result<(), Error> {
-        let TestChainCommand { pid, chain, write, read } = self;
-        let proc = Process::open(pid)?;
-        let address = get_pointer_chain_address(&proc, chain).ok_or("Invalid pointer chain")?;
-        println!("target = {address:x}");
+        let TestChainCommand {
+            pid,
+            remote,
+            core,
+            minidump,
+            image,
+            chain,
+            write,
+            read,
+            width,
+            endian,
+            load_base,
+        } = self;
+        let layout = pointer_layout(width, endian)?;
+        let module_base = module_base(load_base);
 
-        if let Some(size) = read {
-            let mut buf = vec![0; size];
-            proc.read_exact_at(&mut buf, address)?;
-            println!("{}", hex_encode(&buf));
-        }
-
-        if let Some(bytes) = write {
-            proc.write_at(&bytes.0, address)?;
+        match source(pid, remote, core, minidump, image)? {
+            Source::Process(pid) => {
+                let proc = Process::open(pid)?;
+                let address = test_chain(&proc, &chain, layout, module_base, read)?;
+                if let Some(bytes) = write {
+                    proc.write_at(&bytes.0, address)?;
+                }
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            Source::Remote(addr, pid) => {
+                let proc = RemoteProcess::connect(&addr, pid)?;
+                let address = test_chain(&proc, &chain, layout, module_base, read)?;
+                if let Some(bytes) = write {
+                    proc.write_all_at(&bytes.0, address)?;
+                }
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            Source::Core(path) => {
+                if write.is_some() {
+                    return Err("cannot write to a core file".into());
+                }
+                test_chain(&CoreDump::open(path)?, &chain, layout, module_base, read)?;
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            Source::Minidump(path) => {
+                if write.is_some() {
+                    return Err("cannot write to a minidump file".into());
+                }
+                test_chain(&Minidump::open(path)?, &chain, layout, module_base, read)?;
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            Source::Image(path) => {
+                if write.is_some() {
+                    return Err("cannot write to a raw memory image".into());
+                }
+                test_chain(&RawImage::open(path)?, &chain, layout, module_base, read)?;
+            }
+            #[cfg(not(any(target_os = "linux", target_os = "android")))]
+            Source::Core(_) | Source::Minidump(_) | Source::Image(_) => {
+                return Err("dump files are only supported on linux".into())
+            }
+            #[cfg(not(any(target_os = "linux", target_os = "android")))]
+            Source::Remote(..) => return Err("remote processes are only supported on linux".into()),
         }
 
         Ok(())
     }
 }
 
-#[inline]
-fn hex_encode(bytes: &[u8]) -> String {
-    bytes.iter().fold(String::with_capacity(256), |mut output, b| {
-        let _ = write!(output, "{b:02X}");
-        output
-    })
-}
-
-#[inline]
-pub fn get_pointer_chain_address<P, S>(proc: &P, chain: S) -> Option<usize>
+// 输出指针链的解析过程和目标地址，read 不为 None 时读取目标地址的数据
+fn test_chain<P>(
+    proc: &P,
+    chain: &PointerChain,
+    layout: Layout,
+    module_base: ModuleBase,
+    read: Option<usize>,
+) -> Result<usize, Error>
 where
-    P: VirtualMemoryRead + ProcessInfo,
-    S: AsRef<str>,
+    P: ProcessInfo + VirtualMemoryRead,
 {
-    let (a, cs) = chain.as_ref().rsplit_once(']')?;
-    let (name, idx) = a.rsplit_once('[')?;
-    let idx = idx.parse::<usize>().ok()?;
-    let mut iter = cs.split('.');
-    let base = iter.next()?.parse().ok()?;
-    let items = iter.map(|s| s.parse());
-
-    let mut address = find_base_address(proc, name, idx)?.checked_add(base)?;
+    let resolver = ChainResolver::new(proc, module_base)?;
+    let trace = resolver.trace(proc, layout, chain).map_err(ptrsx::Error::from)?;
+    if let Some(name) = &chain.module {
+        println!("{name} + {} = {:x}", chain.offset, trace.base);
+    }
+    trace.steps.iter().for_each(print_step);
+    // 失败时也输出已经完成的步骤
+    let address = trace.target().map_err(ptrsx::Error::from)?;
+    println!("target = {address:x}");
 
-    println!("{name}[{idx}] + {base} = {address:x}");
-    let mut buf = [0; mem::size_of::<usize>()];
-    for item in items {
-        proc.read_exact_at(&mut buf, address).ok()?;
-        let item = item.ok()?;
-        address = usize::from_le_bytes(buf).checked_add_signed(item)?;
-        println!("+ {item} = {address:x}");
+    if let Some(size) = read {
+        let mut buf = vec![0; size];
+        proc.read_exact_at(&mut buf, address)?;
+        println!("{}", hex_encode(&buf));
     }
 
-    Some(address)
+    Ok(address)
 }
 
-struct Module<'a> {
-    start: usize,
-    end: usize,
-    name: &'a str,
+// 输出每一步读取的指针和结果所在的区域
+#[inline]
+fn print_step(step: &ResolveStep) {
+    let ResolveStep { addr, value, offset, result, region } = step;
+    let region = match region {
+        Some(r) => r.name.as_deref().unwrap_or("[anon]"),
+        None => "[unmapped]",
+    };
+    println!("[{addr:x}] = {value:x} + {offset} = {result:x} {region}");
 }
 
 #[inline]
-fn find_base_address<P: ProcessInfo>(proc: &P, name: &str, index: usize) -> Option<usize> {
-    let vqs = proc.get_maps().flatten().collect::<Vec<_>>();
-    vqs.iter()
-        .filter(|x| x.is_write() && x.is_read())
-        .flat_map(|x| Some(Module { start: x.start(), end: x.end(), name: x.name()? }))
-        .fold(Vec::<Module>::with_capacity(vqs.len()), |mut acc, cur| {
-            match acc.last_mut() {
-                Some(last) if last.name == cur.name => last.end = cur.end,
-                _ => acc.push(cur),
-            }
-            acc
-        })
-        .into_iter()
-        .map(|Module { start, end, name }| {
-            let name = Path::new(name).file_name().and_then(|s| s.to_str()).unwrap_or(name);
-            Module { start, end, name }
-        })
-        .filter(|x| x.name.eq(name))
-        .nth(index)
-        .map(|x| x.start)
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::with_capacity(256), |mut output, b| {
+        let _ = write!(output, "{b:02X}");
+        output
+    })
 }
@@ -1,6 +1,7 @@
 use std::path::PathBuf;
 
 use argh::{FromArgValue, FromArgs};
+use ptrsx::{Endian, Layout, ModuleBase, PointerChain};
 use vmmap::Pid;
 
 #[derive(FromArgs)]
@@ -15,35 +16,105 @@ pub struct Commands {
 pub enum CommandEnum {
     DumpProcess(DumpCommand),
     TestChain(TestChainCommand),
+    WriteCore(CoreCommand),
 }
 
 #[derive(FromArgs)]
 #[argh(subcommand, name = "disk", description = "dump process pointer to disk")]
 pub struct DumpCommand {
     #[argh(option, short = 'p', description = "process id")]
-    pub pid: Pid,
+    pub pid: Option<Pid>,
+
+    #[argh(option, description = "agent address of the process, host:port or unix:/path")]
+    pub remote: Option<String>,
+
+    #[argh(option, description = "ELF core file instead of a live process")]
+    pub core: Option<PathBuf>,
+
+    #[argh(option, description = "minidump file of a windows process instead of a live process")]
+    pub minidump: Option<PathBuf>,
+
+    #[argh(option, description = "region table of raw memory images instead of a live process")]
+    pub image: Option<PathBuf>,
 
     #[argh(option, description = "modules info out filename")]
     pub info: Option<PathBuf>,
 
     #[argh(option, description = "binary data out filename")]
     pub bin: Option<PathBuf>,
+
+    #[argh(option, description = "pointer width of target process, 4 or 8")]
+    pub width: Option<usize>,
+
+    #[argh(option, description = "byte order of target process, little or big")]
+    pub endian: Option<EndianArg>,
+
+    #[argh(option, description = "pointer alignment 8, 4 or 1. default pointer width")]
+    pub align: Option<usize>,
+
+    #[argh(
+        option,
+        default = "false",
+        description = "module offsets relative to the load base. default false"
+    )]
+    pub load_base: bool,
 }
 
 #[derive(FromArgs)]
 #[argh(subcommand, name = "test", description = "test pointer chain")]
 pub struct TestChainCommand {
     #[argh(option, short = 'p', description = "process id")]
-    pub pid: Pid,
+    pub pid: Option<Pid>,
 
-    #[argh(option, description = "pointer chain")]
-    pub chain: String,
+    #[argh(option, description = "agent address of the process, host:port or unix:/path")]
+    pub remote: Option<String>,
+
+    #[argh(option, description = "ELF core file instead of a live process")]
+    pub core: Option<PathBuf>,
+
+    #[argh(option, description = "minidump file of a windows process instead of a live process")]
+    pub minidump: Option<PathBuf>,
+
+    #[argh(option, description = "region table of raw memory images instead of a live process")]
+    pub image: Option<PathBuf>,
+
+    #[argh(option, description = "pointer chain, e.g. libc.so.6[0]+0x1d4560.16.-8")]
+    pub chain: PointerChain,
 
     #[argh(option, short = 'w', description = "write bytes")]
     pub write: Option<WVecU8>,
 
     #[argh(option, short = 'r', description = "read bytes")]
     pub read: Option<usize>,
+
+    #[argh(option, description = "pointer width of target process, 4 or 8")]
+    pub width: Option<usize>,
+
+    #[argh(option, description = "byte order of target process, little or big")]
+    pub endian: Option<EndianArg>,
+
+    #[argh(
+        option,
+        default = "false",
+        description = "module offsets relative to the load base. default false"
+    )]
+    pub load_base: bool,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "core", description = "write process memory to an ELF core file")]
+pub struct CoreCommand {
+    #[argh(option, short = 'p', description = "process id")]
+    pub pid: Pid,
+
+    #[argh(option, description = "agent address of the process, host:port or unix:/path")]
+    pub remote: Option<String>,
+
+    #[argh(option, description = "core out filename")]
+    pub out: Option<PathBuf>,
+
+    #[argh(option, default = "false", description = "only write writable regions. default false")]
+    pub writable: bool,
 }
 
 pub struct WVecU8(pub Vec<u8>);
@@ -58,3 +129,60 @@ impl FromArgValue for WVecU8 {
         Ok(Self(bytes))
     }
 }
+
+pub struct EndianArg(pub Endian);
+
+impl FromArgValue for EndianArg {
+    fn from_arg_value(value: &str) -> Result<Self, String> {
+        match value {
+            "little" | "le" => Ok(Self(Endian::Little)),
+            "big" | "be" => Ok(Self(Endian::Big)),
+            _ => Err(format!("invalid byte order: {value}")),
+        }
+    }
+}
+
+// 读取内存的来源
+pub enum Source {
+    Process(Pid),
+    Remote(String, Pid),
+    Core(PathBuf),
+    Minidump(PathBuf),
+    Image(PathBuf),
+}
+
+pub fn source(
+    pid: Option<Pid>,
+    remote: Option<String>,
+    core: Option<PathBuf>,
+    minidump: Option<PathBuf>,
+    image: Option<PathBuf>,
+) -> Result<Source, &'static str> {
+    // --remote 时 --pid 是代理所在机器上的进程
+    let process = match (pid, remote) {
+        (Some(pid), Some(addr)) => Some(Source::Remote(addr, pid)),
+        (None, Some(_)) => return Err("--remote requires --pid"),
+        (pid, None) => pid.map(Source::Process),
+    };
+    let mut sources = (process.into_iter())
+        .chain(core.map(Source::Core))
+        .chain(minidump.map(Source::Minidump))
+        .chain(image.map(Source::Image));
+    match (sources.next(), sources.next()) {
+        (Some(source), None) => Ok(source),
+        _ => Err("exactly one of --pid, --core, --minidump and --image is required"),
+    }
+}
+
+pub fn module_base(load_base: bool) -> ModuleBase {
+    match load_base {
+        true => ModuleBase::Load,
+        false => ModuleBase::Writable,
+    }
+}
+
+pub fn pointer_layout(width: Option<usize>, endian: Option<EndianArg>) -> Result<Layout, ptrsx::Error> {
+    let width = width.unwrap_or(Layout::NATIVE.width());
+    let endian = endian.map_or(Endian::NATIVE, |EndianArg(e)| e);
+    Layout::new(width, endian)
+}
@@ -1,22 +1,76 @@
 use std::path::PathBuf;
 
-use ptrsx::PtrsxScanner;
-use vmmap::Process;
+use ptrsx::{DumpParam, Progress, PtrsxScanner};
+#[cfg(target_os = "macos")]
+use vmmap::macos::cmd::ProcessInfoCmdFixed as ProcessInfo;
+#[cfg(not(target_os = "macos"))]
+use vmmap::ProcessInfo;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use vmmap::{CoreDump, Minidump, RawImage, RemoteProcess};
+use vmmap::{Process, VirtualMemoryRead};
 
-use super::{DumpCommand, Error, Spinner};
+use super::{module_base, pointer_layout, source, DumpCommand, Error, ProgressBar, Source};
 
 impl DumpCommand {
     pub fn init(self) -> Result<(), Error> {
-        let DumpCommand { pid, info, bin } = self;
-        let info = info.unwrap_or_else(|| PathBuf::from(format!("{pid}.info.txt")));
-        let bin = bin.unwrap_or_else(|| PathBuf::from(format!("{pid}.bin")));
-        let mut spinner = Spinner::start("start dump pointers...");
-        let ptrsx = PtrsxScanner::default();
+        let DumpCommand {
+            pid,
+            remote,
+            core,
+            minidump,
+            image,
+            info,
+            bin,
+            width,
+            endian,
+            align,
+            load_base,
+        } = self;
+        let progress = Progress::new();
+        let param = DumpParam {
+            layout: pointer_layout(width, endian)?,
+            align,
+            module_base: module_base(load_base),
+            progress: Some(progress.clone()),
+        };
 
-        let proc = Process::open(pid)?;
-        ptrsx.create_pointer_map(&proc, info, bin)?;
-        spinner.stop("dump is finished.");
-
-        Ok(())
+        match source(pid, remote, core, minidump, image)? {
+            Source::Process(pid) => dump(&Process::open(pid)?, param, progress, info, bin),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            Source::Remote(addr, pid) => dump(&RemoteProcess::connect(&addr, pid)?, param, progress, info, bin),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            Source::Core(path) => dump(&CoreDump::open(path)?, param, progress, info, bin),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            Source::Minidump(path) => dump(&Minidump::open(path)?, param, progress, info, bin),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            Source::Image(path) => dump(&RawImage::open(path)?, param, progress, info, bin),
+            #[cfg(not(any(target_os = "linux", target_os = "android")))]
+            Source::Core(_) | Source::Minidump(_) | Source::Image(_) => {
+                Err("dump files are only supported on linux".into())
+            }
+            #[cfg(not(any(target_os = "linux", target_os = "android")))]
+            Source::Remote(..) => Err("remote processes are only supported on linux".into()),
+        }
     }
 }
+
+fn dump<P>(
+    proc: &P,
+    param: DumpParam,
+    progress: Progress,
+    info: Option<PathBuf>,
+    bin: Option<PathBuf>,
+) -> Result<(), Error>
+where
+    P: ProcessInfo + VirtualMemoryRead,
+{
+    let pid = proc.pid();
+    let info = info.unwrap_or_else(|| PathBuf::from(format!("{pid}.info.txt")));
+    let bin = bin.unwrap_or_else(|| PathBuf::from(format!("{pid}.bin")));
+    let mut bar = ProgressBar::start("dump pointers...", progress);
+    let ptrsx = PtrsxScanner::default();
+    ptrsx.create_pointer_map(proc, param, info, bin)?;
+    bar.stop("dump is finished.");
+
+    Ok(())
+}
@@ -2,6 +2,7 @@ mod chain;
 mod cmd;
 mod dump;
 mod error;
+mod snapshot;
 mod utils;
 
 pub use cmd::*;
@@ -4,6 +4,7 @@ fn main() {
     if let Err(err) = match argh::from_env::<Commands>().cmds {
         CommandEnum::DumpProcess(this) => this.init(),
         CommandEnum::TestChain(this) => this.init(),
+        CommandEnum::WriteCore(this) => this.init(),
     } {
         eprintln!("\n\x1b[31m error: {err} \x1b[0m")
     }
@@ -0,0 +1,242 @@
+use super::{CoreCommand, Error};
+
+impl CoreCommand {
+    pub fn init(self) -> Result<(), Error> {
+        #[cfg(all(
+            any(target_os = "linux", target_os = "android"),
+            any(target_arch = "x86_64", target_arch = "aarch64")
+        ))]
+        {
+            let CoreCommand { pid, remote, out, writable } = self;
+            let out = out.unwrap_or_else(|| format!("{pid}.core").into());
+            let mut spinner = super::Spinner::start("write core file...");
+            match remote {
+                Some(addr) => elf::write_core(&vmmap::RemoteProcess::connect(&addr, pid)?, writable, out)?,
+                None => elf::write_core(&vmmap::Process::open(pid)?, writable, out)?,
+            }
+            spinner.stop("core file is finished.");
+            Ok(())
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        {
+            let _ = self;
+            Err("ELF core files are only supported on linux".into())
+        }
+        // NT_PRSTATUS 的布局和 e_machine 与架构有关
+        #[cfg(all(
+            any(target_os = "linux", target_os = "android"),
+            not(any(target_arch = "x86_64", target_arch = "aarch64"))
+        ))]
+        {
+            let _ = self;
+            Err("ELF core files are only supported on x86_64 and aarch64".into())
+        }
+    }
+}
+
+// 把进程内存按本机字节序写入 ELF64 core 文件，每个内存区域对应一个 PT_LOAD 段，
+// 文件映射写入 NT_FILE 注释，寄存器不保存，NT_PRSTATUS 中只有 pid
+#[cfg(all(
+    any(target_os = "linux", target_os = "android"),
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+mod elf {
+    use std::{
+        fs::File,
+        io::{self, BufWriter, Seek, Write},
+        os::unix::fs::FileExt,
+        path::Path,
+    };
+
+    use vmmap::{linux::VirtualQueryExt, ProcessInfo, VirtualMemoryRead, VirtualQuery};
+
+    #[cfg(target_endian = "little")]
+    const EI_DATA: u8 = 1;
+    #[cfg(target_endian = "big")]
+    const EI_DATA: u8 = 2;
+    const EHDR_SIZE: usize = 64;
+    const PHDR_SIZE: usize = 56;
+    const SHDR_SIZE: usize = 64;
+    // 程序头过多时 e_phnum 为 PN_XNUM，实际数量写入第 0 个节头的 sh_info
+    const PN_XNUM: usize = 0xffff;
+    const PAGE_SIZE: usize = 0x1000;
+    const CHUNK_SIZE: usize = 0x100000;
+    const PT_LOAD: u32 = 1;
+    const PT_NOTE: u32 = 4;
+    const NT_PRSTATUS: u32 = 1;
+    const NT_PRPSINFO: u32 = 3;
+    const NT_FILE: u32 = 0x4649_4c45;
+
+    #[cfg(target_arch = "x86_64")]
+    const MACHINE: u16 = 62;
+    #[cfg(target_arch = "aarch64")]
+    const MACHINE: u16 = 183;
+
+    // elf_prstatus 的大小，pr_pid 在 32 字节处
+    #[cfg(target_arch = "x86_64")]
+    const PRSTATUS_SIZE: usize = 336;
+    #[cfg(target_arch = "aarch64")]
+    const PRSTATUS_SIZE: usize = 392;
+
+    struct Region {
+        start: usize,
+        end: usize,
+        flags: u32,
+        offset: usize,
+        name: Option<String>,
+    }
+
+    pub fn write_core<P, T>(proc: &P, writable: bool, path: T) -> io::Result<()>
+    where
+        P: ProcessInfo + VirtualMemoryRead,
+        T: AsRef<Path>,
+    {
+        let regions = proc
+            .get_maps()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(io::Error::other)?
+            .into_iter()
+            .filter(|m| m.is_read() && (m.is_write() || !writable) && m.size() != 0)
+            .map(|m| Region {
+                start: m.start(),
+                end: m.end(),
+                flags: (m.is_read() as u32) << 2 | (m.is_write() as u32) << 1 | m.is_exec() as u32,
+                offset: m.offset(),
+                name: m.name().map(String::from),
+            })
+            .collect::<Vec<_>>();
+
+        let note = notes(proc, &regions);
+        let phnum = regions.len() + 1;
+        let note_offset = EHDR_SIZE + PHDR_SIZE * phnum;
+        let shdr_offset = (note_offset + note.len()).next_multiple_of(8);
+        let (e_phnum, e_shoff, e_shentsize, e_shnum, header_len) = match phnum < PN_XNUM {
+            true => (phnum, 0, 0, 0, note_offset + note.len()),
+            false => (PN_XNUM, shdr_offset, SHDR_SIZE, 1, shdr_offset + SHDR_SIZE),
+        };
+        let data_offset = header_len.next_multiple_of(PAGE_SIZE);
+
+        // 先写入内存数据，无法读取的部分不写入，filesz 只包含成功读取的字节
+        let file = File::create(path)?;
+        let mut writer = BufWriter::with_capacity(CHUNK_SIZE, &file);
+        writer.seek(io::SeekFrom::Start(data_offset as u64))?;
+        let mut buf = vec![0; CHUNK_SIZE];
+        let mut segments = Vec::with_capacity(regions.len());
+        let mut pos = data_offset;
+        for region in &regions {
+            let offset = pos.next_multiple_of(PAGE_SIZE);
+            writer.seek(io::SeekFrom::Start(offset as u64))?;
+            let mut filesz = 0;
+            for addr in (region.start..region.end).step_by(CHUNK_SIZE) {
+                let size = CHUNK_SIZE.min(region.end - addr);
+                if proc.read_exact_at(&mut buf[..size], addr).is_err() {
+                    break;
+                }
+                writer.write_all(&buf[..size])?;
+                filesz += size;
+            }
+            segments.push((offset, filesz));
+            pos = offset + filesz;
+        }
+        writer.flush()?;
+        drop(writer);
+
+        let mut header = Vec::with_capacity(header_len);
+        header.extend_from_slice(&[0x7f, b'E', b'L', b'F', 2, EI_DATA, 1, 0]);
+        header.extend_from_slice(&[0; 8]);
+        header.extend_from_slice(&4_u16.to_ne_bytes()); // ET_CORE
+        header.extend_from_slice(&MACHINE.to_ne_bytes());
+        header.extend_from_slice(&1_u32.to_ne_bytes());
+        header.extend_from_slice(&0_u64.to_ne_bytes()); // e_entry
+        header.extend_from_slice(&(EHDR_SIZE as u64).to_ne_bytes());
+        header.extend_from_slice(&(e_shoff as u64).to_ne_bytes());
+        header.extend_from_slice(&0_u32.to_ne_bytes());
+        header.extend_from_slice(&(EHDR_SIZE as u16).to_ne_bytes());
+        header.extend_from_slice(&(PHDR_SIZE as u16).to_ne_bytes());
+        header.extend_from_slice(&(e_phnum as u16).to_ne_bytes());
+        header.extend_from_slice(&(e_shentsize as u16).to_ne_bytes());
+        header.extend_from_slice(&(e_shnum as u16).to_ne_bytes());
+        header.extend_from_slice(&[0; 2]);
+
+        phdr(&mut header, PT_NOTE, 0, note_offset, 0, note.len(), 0, 4);
+        for (region, &(offset, filesz)) in regions.iter().zip(&segments) {
+            let memsz = region.end - region.start;
+            phdr(&mut header, PT_LOAD, region.flags, offset, region.start, filesz, memsz, PAGE_SIZE);
+        }
+        header.extend_from_slice(&note);
+        if e_shoff != 0 {
+            // SHT_NULL 节头，sh_size 为节头数量，sh_info 为程序头数量
+            header.resize(e_shoff, 0);
+            let mut shdr = [0; SHDR_SIZE];
+            shdr[0x20..0x28].copy_from_slice(&(e_shnum as u64).to_ne_bytes());
+            shdr[0x2c..0x30].copy_from_slice(&(phnum as u32).to_ne_bytes());
+            header.extend_from_slice(&shdr);
+        }
+        file.write_all_at(&header, 0)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn phdr(
+        buf: &mut Vec<u8>,
+        p_type: u32,
+        flags: u32,
+        offset: usize,
+        vaddr: usize,
+        filesz: usize,
+        memsz: usize,
+        align: usize,
+    ) {
+        buf.extend_from_slice(&p_type.to_ne_bytes());
+        buf.extend_from_slice(&flags.to_ne_bytes());
+        for n in [offset, vaddr, 0, filesz, memsz, align] {
+            buf.extend_from_slice(&(n as u64).to_ne_bytes());
+        }
+    }
+
+    // NT_PRSTATUS NT_PRPSINFO NT_FILE 三个注释
+    fn notes<P: ProcessInfo>(proc: &P, regions: &[Region]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        let pid = proc.pid().to_ne_bytes();
+
+        let mut prstatus = [0; PRSTATUS_SIZE];
+        prstatus[32..36].copy_from_slice(&pid);
+        note(&mut buf, NT_PRSTATUS, &prstatus);
+
+        let path = proc.app_path();
+        let mut prpsinfo = [0; 136];
+        prpsinfo[24..28].copy_from_slice(&pid);
+        let fname = path.file_name().map_or(&[][..], |s| s.as_encoded_bytes());
+        let psargs = path.as_os_str().as_encoded_bytes();
+        prpsinfo[40..40 + fname.len().min(15)].copy_from_slice(&fname[..fname.len().min(15)]);
+        prpsinfo[56..56 + psargs.len().min(79)].copy_from_slice(&psargs[..psargs.len().min(79)]);
+        note(&mut buf, NT_PRPSINFO, &prpsinfo);
+
+        let files = regions
+            .iter()
+            .filter_map(|r| Some((r, r.name.as_deref().filter(|s| s.starts_with('/'))?)))
+            .collect::<Vec<_>>();
+        let mut desc = Vec::new();
+        desc.extend_from_slice(&(files.len() as u64).to_ne_bytes());
+        desc.extend_from_slice(&(PAGE_SIZE as u64).to_ne_bytes());
+        for (r, _) in &files {
+            for n in [r.start, r.end, r.offset / PAGE_SIZE] {
+                desc.extend_from_slice(&(n as u64).to_ne_bytes());
+            }
+        }
+        for (_, name) in &files {
+            desc.extend_from_slice(name.as_bytes());
+            desc.push(0);
+        }
+        note(&mut buf, NT_FILE, &desc);
+        buf
+    }
+
+    fn note(buf: &mut Vec<u8>, n_type: u32, desc: &[u8]) {
+        buf.extend_from_slice(&5_u32.to_ne_bytes());
+        buf.extend_from_slice(&(desc.len() as u32).to_ne_bytes());
+        buf.extend_from_slice(&n_type.to_ne_bytes());
+        buf.extend_from_slice(b"CORE\0\0\0\0");
+        buf.extend_from_slice(desc);
+        buf.resize(buf.len().next_multiple_of(4), 0);
+    }
+}
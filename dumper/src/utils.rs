@@ -1,7 +1,7 @@
 use std::{
     borrow::Cow,
     fmt::Display,
-    io::{stdout, Write},
+    io::{self, stdout, Stdout, Write},
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
@@ -10,6 +10,8 @@ use std::{
     time::{Duration, Instant},
 };
 
+use ptrsx::{Progress, ProgressState};
+
 pub struct Spinner {
     thread_handle: Option<thread::JoinHandle<()>>,
     still_spinning: Arc<AtomicBool>,
@@ -45,3 +47,54 @@ impl Spinner {
         println!("\n\x1B[34m[*]\x1B[0m {msg}")
     }
 }
+
+pub struct ProgressBar {
+    thread_handle: Option<thread::JoinHandle<()>>,
+    still_running: Arc<AtomicBool>,
+}
+
+impl ProgressBar {
+    pub fn start(msg: impl Into<Cow<'static, str>>, progress: Progress) -> Self {
+        let still_running = Arc::new(AtomicBool::new(true));
+        let msg = msg.into();
+        let mut stdout = stdout();
+        let time = Instant::now();
+        let ssp = still_running.clone();
+        let handle = thread::spawn(move || {
+            while ssp.load(Ordering::Relaxed) {
+                draw(&mut stdout, &msg, &progress, time).expect("failed to write to stdout");
+                thread::sleep(Duration::from_millis(100));
+            }
+            draw(&mut stdout, &msg, &progress, time).expect("failed to write to stdout");
+        });
+
+        Self { thread_handle: Some(handle), still_running }
+    }
+
+    pub fn stop(&mut self, msg: impl Display) {
+        self.still_running.store(false, Ordering::Relaxed);
+        self.thread_handle.take().unwrap().join().unwrap();
+        println!("\n\x1B[34m[*]\x1B[0m {msg}")
+    }
+}
+
+fn draw(stdout: &mut Stdout, msg: &str, progress: &Progress, time: Instant) -> io::Result<()> {
+    let ProgressState { bytes, total_bytes, regions, total_regions, .. } = progress.state();
+    let (mib, total_mib) = (bytes as f64 / 1048576.0, total_bytes as f64 / 1048576.0);
+    let info = format!("{mib:.1}/{total_mib:.1} MiB  regions: {regions}/{total_regions}");
+    draw_bar(stdout, msg, bytes, total_bytes, &info, time)
+}
+
+fn draw_bar(stdout: &mut Stdout, msg: &str, done: u64, total: u64, info: &str, time: Instant) -> io::Result<()> {
+    const WIDTH: usize = 30;
+    let ratio = match total {
+        0 => 0.0,
+        n => (done as f64 / n as f64).min(1.0),
+    };
+    let fill = (ratio * WIDTH as f64) as usize;
+    let bar = format!("{}{}", "=".repeat(fill), " ".repeat(WIDTH - fill));
+    let percent = ratio * 100.0;
+    let elapsed = time.elapsed().as_secs_f32();
+    write!(stdout, "\r\x1B[34m[{bar}]\x1B[0m {percent:>5.1}% {msg}  {info}  Time: {elapsed:.1}s\x1B[K")?;
+    stdout.flush()
+}
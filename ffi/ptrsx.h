@@ -2,6 +2,19 @@
 
 typedef struct PointerScanTool PointerScanTool;
 
+typedef struct ProgressInfo {
+  uint64_t bytes;
+  uint64_t total_bytes;
+  uint64_t regions;
+  uint64_t total_regions;
+  uint64_t branches;
+  uint64_t total_branches;
+  uint64_t nodes;
+  uint64_t chains;
+} ProgressInfo;
+
+typedef bool (*ProgressCallback)(const struct ProgressInfo *info, void *userdata);
+
 typedef struct Param {
   size_t addr;
   size_t depth;
@@ -22,6 +35,17 @@ const char *get_last_error(void);
 
 int ptrs_set_proc(struct PointerScanTool *ptr, int pid);
 
+int ptrs_set_core(struct PointerScanTool *ptr, const char *path);
+
+int ptrs_set_pointer_layout(struct PointerScanTool *ptr, size_t width,
+                            bool big_endian);
+
+int ptrs_set_module_base(struct PointerScanTool *ptr, bool load_base);
+
+int ptrs_set_progress_callback(struct PointerScanTool *ptr,
+                               ProgressCallback callback,
+                               void *userdata);
+
 int ptrs_create_pointer_map(struct PointerScanTool *ptr, const char *info_path,
                             const char *bin_path);
 
@@ -31,6 +55,10 @@ int ptrs_load_pointer_map(struct PointerScanTool *ptr, const char *info_path,
 int ptrs_scan_pointer_chain(struct PointerScanTool *ptr, struct Param param,
                             const char *file_path);
 
+int ptrs_scan_pointer_chain_ex(struct PointerScanTool *ptr, struct Param param,
+                               const char *include, const char *exclude,
+                               const char *file_path);
+
 int compare_two_file(const char *file1, const char *file2, const char *outfile);
 
 int ptrs_get_chain_addr(struct PointerScanTool *ptr, const char *chain,
@@ -1,4 +1,5 @@
 from ctypes import (
+    CFUNCTYPE,
     POINTER,
     Structure,
     byref,
@@ -8,12 +9,13 @@ from ctypes import (
     c_size_t,
     c_ssize_t,
     c_ubyte,
+    c_uint64,
     c_void_p,
     cdll,
     pointer,
     sizeof,
 )
-from typing import Optional
+from typing import Callable, List, Optional
 
 
 class Param(Structure):
@@ -29,6 +31,10 @@ class Param(Structure):
         ("_last", POINTER(c_ssize_t)),
     ]
 
+    # Not part of the C struct, passed separately to `ptrs_scan_pointer_chain_ex`
+    _include: Optional[bytes] = None
+    _exclude: Optional[bytes] = None
+
     def addr(self, value: int):
         self._addr = c_size_t(value)
         return self
@@ -74,6 +80,38 @@ class Param(Structure):
             self._last = pointer(c_ssize_t(value))
         return self
 
+    # Only use these modules as base, exact names or globs such as "libc*"
+    def include(self, value: Optional[List[str]]):
+        if value is None:
+            self._include = None
+        else:
+            self._include = ",".join(value).encode()
+        return self
+
+    # Never use these modules as base, exact names or globs such as "[stack]*"
+    def exclude(self, value: Optional[List[str]]):
+        if value is None:
+            self._exclude = None
+        else:
+            self._exclude = ",".join(value).encode()
+        return self
+
+
+class ProgressInfo(Structure):
+    _fields_ = [
+        ("bytes", c_uint64),
+        ("total_bytes", c_uint64),
+        ("regions", c_uint64),
+        ("total_regions", c_uint64),
+        ("branches", c_uint64),
+        ("total_branches", c_uint64),
+        ("nodes", c_uint64),
+        ("chains", c_uint64),
+    ]
+
+
+ProgressCallback = CFUNCTYPE(c_bool, POINTER(ProgressInfo), c_void_p)
+
 
 class PointerScanTool:
 
@@ -83,6 +121,16 @@ class PointerScanTool:
         "ptrs_free": (None, POINTER(c_void_p)),
         # set pid
         "ptrs_set_proc": (c_int, POINTER(c_void_p), c_int),
+        # linux only
+        "ptrs_set_core": (c_int, POINTER(c_void_p), c_char_p),
+        "ptrs_set_pointer_layout": (c_int, POINTER(c_void_p), c_size_t, c_bool),
+        "ptrs_set_module_base": (c_int, POINTER(c_void_p), c_bool),
+        "ptrs_set_progress_callback": (
+            c_int,
+            POINTER(c_void_p),
+            ProgressCallback,
+            c_void_p,
+        ),
         # scan pointer chain
         "ptrs_create_pointer_map": (
             c_int,
@@ -92,6 +140,14 @@ class PointerScanTool:
         ),
         "ptrs_load_pointer_map": (c_int, POINTER(c_void_p), c_char_p, c_char_p),
         "ptrs_scan_pointer_chain": (c_int, POINTER(c_void_p), Param, c_char_p),
+        "ptrs_scan_pointer_chain_ex": (
+            c_int,
+            POINTER(c_void_p),
+            Param,
+            c_char_p,
+            c_char_p,
+            c_char_p,
+        ),
         "refresh_modules_cache": (c_int, POINTER(c_void_p)),
         # verify pointer chain
         "ptrs_filter_invalid": (c_int, POINTER(c_void_p), c_char_p, c_char_p),
@@ -118,6 +174,9 @@ class PointerScanTool:
 
     def _init_lib_functions(self):
         for k, v in self.LIBRARY_FUNCS.items():
+            # Platform specific functions are missing from other builds
+            if not hasattr(self._lib, k):
+                continue
             f = getattr(self._lib, k)
             f.restype = v[0]
             f.argtypes = v[1:]
@@ -143,6 +202,38 @@ class PointerScanTool:
         ret = self._lib.ptrs_set_proc(self._ptr, c_int(pid))
         self._check_ret(ret)
 
+    # Read memory from an ELF core file instead of a process, Linux only
+    def set_core(self, path: str):
+        ret = self._lib.ptrs_set_core(self._ptr, c_char_p(path.encode()))
+        self._check_ret(ret)
+
+    # Set pointer width (4 or 8) and byte order of the target process
+    def set_pointer_layout(self, width: int, big_endian: bool = False):
+        ret = self._lib.ptrs_set_pointer_layout(
+            self._ptr, c_size_t(width), c_bool(big_endian)
+        )
+        self._check_ret(ret)
+
+    # Module offsets relative to the load base (True) or the first writable mapping (False)
+    # Call `refresh_modules_cache` again after changing it, fails if a loaded pointer file uses the other base
+    def set_module_base(self, load_base: bool):
+        ret = self._lib.ptrs_set_module_base(self._ptr, c_bool(load_base))
+        self._check_ret(ret)
+
+    # Set a callback that receives `ProgressInfo` while creating pointer maps or scanning
+    # It is called from another thread, return True to cancel, None to remove it
+    def set_progress_callback(self, callback: Optional[Callable[[ProgressInfo], bool]]):
+        if callback is None:
+            self._progress_callback = ProgressCallback()
+        else:
+            self._progress_callback = ProgressCallback(
+                lambda info, _: bool(callback(info.contents))
+            )
+        ret = self._lib.ptrs_set_progress_callback(
+            self._ptr, self._progress_callback, None
+        )
+        self._check_ret(ret)
+
     # Create a pointer map and write pointer information to `info_file` and `bin_file`
     def create_pointer_map(self, info_file: str, bin_file: str):
         ret = self._lib.ptrs_create_pointer_map(
@@ -153,6 +244,7 @@ class PointerScanTool:
         self._check_ret(ret)
 
     # Load the pointer file created by `self.create_pointer_map`
+    # The module base switches to the one recorded in the pointer file
     def load_pointer_map(self, info_file: str, bin_file: str):
         ret = self._lib.ptrs_load_pointer_map(
             self._ptr, c_char_p(info_file.encode()), c_char_p(bin_file.encode())
@@ -162,8 +254,12 @@ class PointerScanTool:
     # Scan the pointer chain and write the results to `outfile`
     # If there are multiple target addresses, you can use it in multiple threads, not sure if it is thread safe for now
     def scan_pointer_chain(self, param: Param, outfile: str):
-        ret = self._lib.ptrs_scan_pointer_chain(
-            self._ptr, param, c_char_p(outfile.encode())
+        ret = self._lib.ptrs_scan_pointer_chain_ex(
+            self._ptr,
+            param,
+            param._include,
+            param._exclude,
+            c_char_p(outfile.encode()),
         )
         self._check_ret(ret)
 
@@ -2,19 +2,26 @@
 
 use core::{
     cell::RefCell,
-    ffi::{c_char, c_int, CStr},
-    mem, ptr,
+    ffi::{c_char, c_int, c_void, CStr},
+    ptr,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
 };
 use std::{
-    collections::{HashMap, HashSet},
+    collections::HashSet,
     ffi::CString,
     fs::File,
     io::{BufRead, BufReader, BufWriter, Write},
-    path::Path,
+    thread,
 };
 
-use ptrsx::{Module, PtrsxScanner, UserParam};
-use vmmap::{Pid, Process, ProcessInfo, VirtualMemoryRead, VirtualQuery};
+use ptrsx::{
+    ChainResolver, DumpParam, Endian, Layout, ModuleBase, PointerChain, Progress, ProgressState, PtrsxScanner,
+    UserParam,
+};
+#[cfg(target_os = "linux")]
+use vmmap::{CoreDump, Mapping, ProcessInfo};
+use vmmap::{Pid, Process, VirtualMemoryRead};
 
 thread_local! {
     static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) }
@@ -39,6 +46,47 @@ pub struct Param {
     pub last: Option<ptr::NonNull<isize>>,
 }
 
+// 创建指针文件和扫描指针链的进度
+#[repr(C)]
+pub struct ProgressInfo {
+    pub bytes: u64,
+    pub total_bytes: u64,
+    pub regions: u64,
+    pub total_regions: u64,
+    pub branches: u64,
+    pub total_branches: u64,
+    pub nodes: u64,
+    pub chains: u64,
+}
+
+impl From<ProgressState> for ProgressInfo {
+    fn from(value: ProgressState) -> Self {
+        let ProgressState {
+            bytes,
+            total_bytes,
+            regions,
+            total_regions,
+            branches,
+            total_branches,
+            nodes,
+            chains,
+        } = value;
+        Self {
+            bytes,
+            total_bytes,
+            regions,
+            total_regions,
+            branches,
+            total_branches,
+            nodes,
+            chains,
+        }
+    }
+}
+
+// 返回 true 取消当前任务
+pub type ProgressCallback = unsafe extern "C" fn(info: *const ProgressInfo, userdata: *mut c_void) -> bool;
+
 const PTR_NULL: &str = "ptr is null";
 const NO_OPEN_PROCESS: &str = "no process is opened";
 const CACHE_INVALID: &str = "modules cache invalid";
@@ -92,11 +140,108 @@ macro_rules! error {
     };
 }
 
+// 读取内存的来源，core 文件中的内存只能读取
+#[cfg(target_os = "linux")]
+enum Source {
+    Process(Process),
+    Core(CoreDump),
+}
+
+#[cfg(not(target_os = "linux"))]
+type Source = Process;
+
+#[cfg(target_os = "linux")]
+impl From<Process> for Source {
+    fn from(value: Process) -> Self {
+        Self::Process(value)
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl VirtualMemoryRead for Source {
+    fn read_at(&self, buf: &mut [u8], offset: usize) -> vmmap::Result<usize> {
+        match self {
+            Source::Process(proc) => proc.read_at(buf, offset),
+            Source::Core(core) => core.read_at(buf, offset),
+        }
+    }
+
+    fn read_exact_at(&self, buf: &mut [u8], offset: usize) -> vmmap::Result<()> {
+        match self {
+            Source::Process(proc) => proc.read_exact_at(buf, offset),
+            Source::Core(core) => core.read_exact_at(buf, offset),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl ProcessInfo for Source {
+    fn pid(&self) -> Pid {
+        match self {
+            Source::Process(proc) => proc.pid(),
+            Source::Core(core) => core.pid(),
+        }
+    }
+
+    fn app_path(&self) -> &std::path::Path {
+        match self {
+            Source::Process(proc) => proc.app_path(),
+            Source::Core(core) => core.app_path(),
+        }
+    }
+
+    fn get_maps(&self) -> impl Iterator<Item = vmmap::Result<Mapping>> {
+        match self {
+            Source::Process(proc) => Box::new(proc.get_maps()) as Box<dyn Iterator<Item = _>>,
+            Source::Core(core) => Box::new(core.get_maps()),
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct PointerScanTool {
     scan: PtrsxScanner,
-    proc: Option<Process>,
-    index: Option<HashMap<String, usize>>,
+    proc: Option<Source>,
+    index: Option<ChainResolver>,
+    layout: Layout,
+    module_base: ModuleBase,
+    callback: Option<Callback>,
+}
+
+#[derive(Clone, Copy)]
+struct Callback(ProgressCallback, *mut c_void);
+
+// userdata 由调用者保证可以在其他线程中使用
+unsafe impl Send for Callback {}
+
+// 任务运行期间在后台线程中每 100ms 调用一次回调，任务结束后再调用一次
+fn with_progress<T>(callback: Option<Callback>, f: impl FnOnce(Option<Progress>) -> T) -> T {
+    let Some(callback) = callback else {
+        return f(None);
+    };
+    let progress = Progress::new();
+    let done = AtomicBool::new(false);
+    thread::scope(|s| {
+        let (progress, done) = (&progress, &done);
+        let handle = s.spawn(move || {
+            let callback = callback;
+            loop {
+                let finished = done.load(Ordering::Acquire);
+                let info = ProgressInfo::from(progress.state());
+                if unsafe { (callback.0)(&info, callback.1) } {
+                    progress.cancel();
+                }
+                if finished {
+                    break;
+                }
+                thread::park_timeout(Duration::from_millis(100));
+            }
+        });
+        let ret = f(Some(progress.clone()));
+        done.store(true, Ordering::Release);
+        handle.thread().unpark();
+        ret
+    })
 }
 
 #[no_mangle]
@@ -124,7 +269,56 @@ pub unsafe extern "C" fn get_last_error() -> *const c_char {
 pub unsafe extern "C" fn ptrs_set_proc(ptr: *mut PointerScanTool, pid: Pid) -> c_int {
     let proc = error!(Process::open(pid));
     let this = null_ptr!(ptr.as_mut());
-    this.proc = Some(proc);
+    this.proc = Some(proc.into());
+    0
+}
+
+// 使用 ELF core 文件代替进程，之后的扫描和指针链解析都读取 core 文件
+#[cfg(target_os = "linux")]
+#[no_mangle]
+pub unsafe extern "C" fn ptrs_set_core(ptr: *mut PointerScanTool, path: *const c_char) -> c_int {
+    let path = error!(CStr::from_ptr(null_ptr!(path.as_ref())).to_str());
+    dbg!(path);
+    let core = error!(CoreDump::open(path));
+    let this = null_ptr!(ptr.as_mut());
+    this.proc = Some(Source::Core(core));
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ptrs_set_pointer_layout(ptr: *mut PointerScanTool, width: usize, big_endian: bool) -> c_int {
+    let endian = if big_endian { Endian::Big } else { Endian::Little };
+    let layout = error!(Layout::new(width, endian));
+    let this = null_ptr!(ptr.as_mut());
+    this.layout = layout;
+    0
+}
+
+// load_base 为 true 时 module+offset
+// 相对于模块的加载地址，否则相对于第一个可读写区域
+// 同时影响创建指针文件和解析指针链，修改后需要重新调用 refresh_modules_cache
+// 已加载的指针文件使用其他方式时返回错误
+#[no_mangle]
+pub unsafe extern "C" fn ptrs_set_module_base(ptr: *mut PointerScanTool, load_base: bool) -> c_int {
+    let this = null_ptr!(ptr.as_mut());
+    let module_base = match load_base {
+        true => ModuleBase::Load,
+        false => ModuleBase::Writable,
+    };
+    error!(this.scan.check_module_base(module_base));
+    this.module_base = module_base;
+    0
+}
+
+// callback 为 NULL 时取消设置，回调在其他线程中调用
+#[no_mangle]
+pub unsafe extern "C" fn ptrs_set_progress_callback(
+    ptr: *mut PointerScanTool,
+    callback: Option<ProgressCallback>,
+    userdata: *mut c_void,
+) -> c_int {
+    let this = null_ptr!(ptr.as_mut());
+    this.callback = callback.map(|cb| Callback(cb, userdata));
     0
 }
 
@@ -141,7 +335,15 @@ pub unsafe extern "C" fn ptrs_create_pointer_map(
 
     let this = null_ptr!(ptr.as_ref());
     let proc = ref_proc!(this.proc.as_ref());
-    error!(this.scan.create_pointer_map(proc, info_file, bin_file));
+    error!(with_progress(this.callback, |progress| {
+        let param = DumpParam {
+            layout: this.layout,
+            align: None,
+            module_base: this.module_base,
+            progress,
+        };
+        this.scan.create_pointer_map(proc, param, info_file, bin_file)
+    }));
 
     0
 }
@@ -152,7 +354,8 @@ pub unsafe extern "C" fn ptrs_load_pointer_map(
     info_path: *const c_char,
     bin_path: *const c_char,
 ) -> c_int {
-    let scan = &mut null_ptr!(ptr.as_mut()).scan;
+    let this = null_ptr!(ptr.as_mut());
+    let scan = &mut this.scan;
     let info_path = error!(CStr::from_ptr(null_ptr!(info_path.as_ref())).to_str());
     dbg!(info_path);
     let file = error!(File::open(info_path));
@@ -161,18 +364,51 @@ pub unsafe extern "C" fn ptrs_load_pointer_map(
     let bin_path = error!(CStr::from_ptr(null_ptr!(bin_path.as_ref())).to_str());
     dbg!(bin_path);
 
-    let file = error!(File::open(bin_path));
-    error!(scan.load_pointer_map(file));
+    error!(scan.load_pointer_map_file(bin_path));
+
+    // 扫描出的指针链使用指针文件的模块基址，解析时也要使用相同的方式
+    if let Some(module_base) = scan.module_base().filter(|&x| x != this.module_base) {
+        this.module_base = module_base;
+        if let (Some(proc), Some(_)) = (this.proc.as_ref(), this.index.as_ref()) {
+            this.index = Some(error!(ChainResolver::new(proc, module_base)));
+        }
+    }
     0
 }
 
+unsafe fn module_list(ptr: *const c_char) -> Result<Vec<String>, core::str::Utf8Error> {
+    let Some(ptr) = ptr.as_ref() else {
+        return Ok(Vec::new());
+    };
+    let list = CStr::from_ptr(ptr).to_str()?;
+    Ok(list
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect())
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn ptrs_scan_pointer_chain(
     ptr: *mut PointerScanTool,
     param: Param,
     file_path: *const c_char,
 ) -> c_int {
-    let scan = &null_ptr!(ptr.as_ref()).scan;
+    ptrs_scan_pointer_chain_ex(ptr, param, ptr::null(), ptr::null(), file_path)
+}
+
+// include/exclude 为逗号分隔的基址模块名或通配符，NULL 表示不限制
+#[no_mangle]
+pub unsafe extern "C" fn ptrs_scan_pointer_chain_ex(
+    ptr: *mut PointerScanTool,
+    param: Param,
+    include: *const c_char,
+    exclude: *const c_char,
+    file_path: *const c_char,
+) -> c_int {
+    let this = null_ptr!(ptr.as_ref());
+    let scan = &this.scan;
 
     let file_name = error!(CStr::from_ptr(null_ptr!(file_path.as_ref())).to_str());
 
@@ -180,13 +416,32 @@ pub unsafe extern "C" fn ptrs_scan_pointer_chain(
     let node = node.map(|x| x.as_ref()).copied();
     let max = max.map(|x| x.as_ref()).copied();
     let last = last.map(|x| x.as_ref()).copied();
+    let include = error!(module_list(include));
+    let exclude = error!(module_list(exclude));
 
-    dbg!(depth, addr, left, right, use_module, node, max, last);
+    dbg!(depth, addr, left, right, use_module, node, max, last, &include, &exclude);
 
     let range = (left, right);
-    let param = UserParam { depth, addr, range, use_module, use_cycle, node, max, last };
-
-    error!(scan.pointer_chain_scanner(param, file_name));
+    error!(with_progress(this.callback, |progress| {
+        let param = UserParam {
+            depth,
+            addr,
+            range,
+            use_module,
+            use_cycle,
+            node,
+            max,
+            last,
+            pattern: None,
+            offset_align: None,
+            include,
+            exclude,
+            shortest: false,
+            threads: None,
+            progress,
+        };
+        scan.pointer_chain_scanner(param, file_name)
+    }));
 
     0
 }
@@ -213,22 +468,14 @@ pub unsafe extern "C" fn compare_two_file(file1: *const c_char, file2: *const c_
     0
 }
 
-fn get_pointer_chain_address<P>(proc: &P, index: &HashMap<String, usize>, chain: &str) -> Option<usize>
+// 文件中的一行，无法解析的指针链视为无效
+#[inline]
+fn get_line_chain_address<P>(proc: &P, index: &ChainResolver, line: &str, layout: Layout) -> Option<usize>
 where
-    P: VirtualMemoryRead + ProcessInfo,
+    P: VirtualMemoryRead,
 {
-    let (module_name, cs) = chain.rsplit_once('+')?;
-    let mut iter = cs.split('.');
-    let base = iter.next()?.parse().ok()?;
-    let items = iter.map(|s| s.parse());
-    let mut address = index.get(module_name)?.checked_add(base)?;
-    let mut buf = [0; mem::size_of::<usize>()];
-    for item in items {
-        proc.read_exact_at(&mut buf, address).ok()?;
-        let item = item.ok()?;
-        address = usize::from_le_bytes(buf).checked_add_signed(item)?;
-    }
-    Some(address)
+    let chain = line.parse::<PointerChain>().ok()?;
+    index.resolve(proc, layout, &chain).ok()
 }
 
 #[no_mangle]
@@ -244,13 +491,14 @@ pub unsafe extern "C" fn ptrs_get_chain_addr(
 
     dbg!(chain);
 
-    match get_pointer_chain_address(proc, index, chain) {
-        Some(ad) => {
+    let chain = error!(chain.parse::<PointerChain>());
+    match index.resolve(proc, ptr.layout, &chain) {
+        Ok(ad) => {
             addr.write(ad);
             0
         }
-        None => {
-            set_last_error(PTR_CHAIN_INVALID);
+        Err(err) => {
+            set_last_error(format!("{PTR_CHAIN_INVALID}: {err}"));
             -1
         }
     }
@@ -283,7 +531,7 @@ pub unsafe extern "C" fn ptrs_filter_invalid(
         if size == 0 {
             break;
         }
-        if get_pointer_chain_address(proc, index, line_buf.trim()).is_some() {
+        if get_line_chain_address(proc, index, line_buf.trim(), ptr.layout).is_some() {
             error!(writer.write_all(line_buf.as_bytes()))
         }
         line_buf.clear()
@@ -325,7 +573,7 @@ pub unsafe extern "C" fn ptrs_filter_value(
             break;
         }
 
-        if get_pointer_chain_address(proc, index, line_buf.trim())
+        if get_line_chain_address(proc, index, line_buf.trim(), ptr.layout)
             .and_then(|addr| proc.read_exact_at(&mut value_buf, addr).ok())
             .is_some_and(|_| value_buf == value)
         {
@@ -343,29 +591,7 @@ pub unsafe extern "C" fn refresh_modules_cache(ptr: *mut PointerScanTool) -> c_i
     let ptr = null_ptr!(ptr.as_mut());
     let proc = ref_proc!(ptr.proc.as_ref());
 
-    let mappings = proc
-        .get_maps()
-        .flatten()
-        .filter(|x| x.is_write() && x.is_read())
-        .collect::<Vec<_>>();
-    let modules = mappings
-        .iter()
-        .flat_map(|x| Some(Module { start: x.start(), end: x.end(), name: x.name()? }))
-        .fold(Vec::<Module>::with_capacity(mappings.len()), |mut acc, cur| {
-            match acc.last_mut() {
-                Some(last) if last.name == cur.name => last.end = cur.end,
-                _ => acc.push(cur),
-            }
-            acc
-        })
-        .into_iter()
-        .map(|Module { start, name, .. }| {
-            let name = Path::new(name).file_name().and_then(|s| s.to_str()).unwrap_or(name);
-            (name.to_string(), start)
-        })
-        .collect::<HashMap<String, usize>>();
-
-    ptr.index = Some(modules);
+    ptr.index = Some(error!(ChainResolver::new(proc, ptr.module_base)));
 
     0
 }
@@ -398,7 +624,7 @@ pub unsafe extern "C" fn ptrs_filter_addr(
             break;
         }
 
-        if get_pointer_chain_address(proc, index, line_buf.trim()).is_some_and(|x| x == addr) {
+        if get_line_chain_address(proc, index, line_buf.trim(), ptr.layout).is_some_and(|x| x == addr) {
             error!(writer.write_all(line_buf.as_bytes()));
         }
 
@@ -0,0 +1,132 @@
+use core::ops::ControlFlow;
+
+use super::pointer_scan::Chain;
+
+// 指针链过滤器，可以任意顺序组合后交给扫描器使用
+// Continue(Some) 保留，可以返回修改后的指针链
+// Continue(None) 丢弃，Break 结束整个扫描
+pub trait ChainFilter {
+    fn filter<'a>(&mut self, chain: Chain<'a>) -> ControlFlow<(), Option<Chain<'a>>>;
+}
+
+impl ChainFilter for () {
+    #[inline]
+    fn filter<'a>(&mut self, chain: Chain<'a>) -> ControlFlow<(), Option<Chain<'a>>> {
+        ControlFlow::Continue(Some(chain))
+    }
+}
+
+impl<F: ChainFilter> ChainFilter for Option<F> {
+    #[inline]
+    fn filter<'a>(&mut self, chain: Chain<'a>) -> ControlFlow<(), Option<Chain<'a>>> {
+        match self {
+            Some(f) => f.filter(chain),
+            None => ControlFlow::Continue(Some(chain)),
+        }
+    }
+}
+
+impl<A: ChainFilter, B: ChainFilter> ChainFilter for (A, B) {
+    #[inline]
+    fn filter<'a>(&mut self, chain: Chain<'a>) -> ControlFlow<(), Option<Chain<'a>>> {
+        match self.0.filter(chain)? {
+            Some(chain) => self.1.filter(chain),
+            None => ControlFlow::Continue(None),
+        }
+    }
+}
+
+impl<F: ChainFilter> ChainFilter for Vec<F> {
+    #[inline]
+    fn filter<'a>(&mut self, chain: Chain<'a>) -> ControlFlow<(), Option<Chain<'a>>> {
+        let mut chain = chain;
+        for f in self.iter_mut() {
+            match f.filter(chain)? {
+                Some(c) => chain = c,
+                None => return ControlFlow::Continue(None),
+            }
+        }
+        ControlFlow::Continue(Some(chain))
+    }
+}
+
+impl<F: ChainFilter + ?Sized> ChainFilter for Box<F> {
+    #[inline]
+    fn filter<'a>(&mut self, chain: Chain<'a>) -> ControlFlow<(), Option<Chain<'a>>> {
+        (**self).filter(chain)
+    }
+}
+
+impl<F: ChainFilter + ?Sized> ChainFilter for &mut F {
+    #[inline]
+    fn filter<'a>(&mut self, chain: Chain<'a>) -> ControlFlow<(), Option<Chain<'a>>> {
+        (**self).filter(chain)
+    }
+}
+
+// 只保留长度大于等于 n 的指针链
+pub struct MinLength(pub usize);
+
+impl ChainFilter for MinLength {
+    #[inline]
+    fn filter<'a>(&mut self, chain: Chain<'a>) -> ControlFlow<(), Option<Chain<'a>>> {
+        ControlFlow::Continue((chain.len() >= self.0).then_some(chain))
+    }
+}
+
+// 必须以指定偏移结束
+pub struct LastOffset(pub isize);
+
+impl ChainFilter for LastOffset {
+    #[inline]
+    fn filter<'a>(&mut self, chain: Chain<'a>) -> ControlFlow<(), Option<Chain<'a>>> {
+        ControlFlow::Continue(chain.last().is_some_and(|o| self.0.eq(o)).then_some(chain))
+    }
+}
+
+// 去除指针链中的循环引用
+pub struct StripCycle;
+
+impl ChainFilter for StripCycle {
+    #[inline]
+    fn filter<'a>(&mut self, chain: Chain<'a>) -> ControlFlow<(), Option<Chain<'a>>> {
+        ControlFlow::Continue(Some(chain.strip_cycle()))
+    }
+}
+
+// 限制最大指针链结果数量，达到数量后结束扫描
+pub struct MaxCount {
+    max: usize,
+    count: usize,
+}
+
+impl MaxCount {
+    pub const fn new(max: usize) -> Self {
+        Self { max, count: 0 }
+    }
+
+    pub const fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl ChainFilter for MaxCount {
+    #[inline]
+    fn filter<'a>(&mut self, chain: Chain<'a>) -> ControlFlow<(), Option<Chain<'a>>> {
+        if self.count >= self.max {
+            return ControlFlow::Break(());
+        }
+        self.count += 1;
+        ControlFlow::Continue(Some(chain))
+    }
+}
+
+// 使用闭包作为过滤器，返回 false 丢弃指针链
+pub struct FilterFn<F>(pub F);
+
+impl<F: FnMut(&Chain) -> bool> ChainFilter for FilterFn<F> {
+    #[inline]
+    fn filter<'a>(&mut self, chain: Chain<'a>) -> ControlFlow<(), Option<Chain<'a>>> {
+        ControlFlow::Continue((self.0)(&chain).then_some(chain))
+    }
+}
@@ -0,0 +1,82 @@
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+};
+
+const PT_LOAD: u64 = 1;
+const PF_W: u64 = 2;
+
+// ELF 文件中的 PT_LOAD 段
+pub struct LoadSegment {
+    pub vaddr: usize,
+    pub memsz: usize,
+    pub writable: bool,
+}
+
+// 读取 ELF 文件的全部 PT_LOAD 段，不是 ELF 文件或者格式错误时返回 None
+pub fn load_segments<P: AsRef<Path>>(path: P) -> Option<Vec<LoadSegment>> {
+    let mut file = File::open(path).ok()?;
+    let mut ehdr = [0; 64];
+    file.read_exact(&mut ehdr[..52]).ok()?;
+    if ehdr[..4] != [0x7f, b'E', b'L', b'F'] {
+        return None;
+    }
+    let is64 = match ehdr[4] {
+        1 => false,
+        2 => true,
+        _ => return None,
+    };
+    let le = match ehdr[5] {
+        1 => true,
+        2 => false,
+        _ => return None,
+    };
+    if is64 {
+        file.read_exact(&mut ehdr[52..]).ok()?;
+    }
+
+    // 按 ELF 的字节序读取 size 字节的整数
+    let read = |buf: &[u8], pos: usize, size: usize| {
+        let bytes = buf.get(pos..pos + size)?;
+        let mut n = [0; 8];
+        match le {
+            true => {
+                n[..size].copy_from_slice(bytes);
+                Some(u64::from_le_bytes(n))
+            }
+            false => {
+                n[8 - size..].copy_from_slice(bytes);
+                Some(u64::from_be_bytes(n))
+            }
+        }
+    };
+
+    let (phoff, phentsize, phnum) = match is64 {
+        true => (read(&ehdr, 0x20, 8)?, read(&ehdr, 0x36, 2)?, read(&ehdr, 0x38, 2)?),
+        false => (read(&ehdr, 0x1c, 4)?, read(&ehdr, 0x2a, 2)?, read(&ehdr, 0x2c, 2)?),
+    };
+    let (phentsize, phnum) = (phentsize as usize, phnum as usize);
+    if phentsize < if is64 { 0x38 } else { 0x20 } {
+        return None;
+    }
+
+    let mut phdrs = vec![0; phentsize * phnum];
+    file.seek(SeekFrom::Start(phoff)).ok()?;
+    file.read_exact(&mut phdrs).ok()?;
+
+    let mut segments = Vec::new();
+    for ph in phdrs.chunks_exact(phentsize) {
+        let (p_type, flags, vaddr, memsz) = match is64 {
+            true => (read(ph, 0, 4)?, read(ph, 4, 4)?, read(ph, 0x10, 8)?, read(ph, 0x28, 8)?),
+            false => (read(ph, 0, 4)?, read(ph, 0x18, 4)?, read(ph, 8, 4)?, read(ph, 0x14, 4)?),
+        };
+        if p_type != PT_LOAD {
+            continue;
+        }
+        let vaddr = usize::try_from(vaddr).ok()?;
+        let memsz = usize::try_from(memsz).ok()?;
+        segments.push(LoadSegment { vaddr, memsz, writable: flags & PF_W != 0 });
+    }
+    Some(segments)
+}
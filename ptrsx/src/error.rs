@@ -1,7 +1,33 @@
+use super::{modules::ModuleBase, pointer_chain::ParseChainError, resolver::ResolveError};
+
 #[derive(Debug)]
 pub enum Error {
     Vm(vmmap::Error),
     Io(std::io::Error),
+    // 不支持的指针文件版本
+    Version(u32),
+    // 不支持的指针宽度
+    PointerWidth(usize),
+    // 不支持的指针对齐
+    Align(usize),
+    // 指针文件不完整
+    Truncated,
+    // 指针文件校验和错误
+    Checksum,
+    // 指针文件内容损坏
+    Corrupted,
+    // 模块信息与指针文件不匹配
+    Mismatch,
+    // 指针文件的模块基址计算方式与预期不同，值为指针文件使用的方式
+    ModuleBase(ModuleBase),
+    // 已取消
+    Cancelled,
+    // 无法解析的偏移模式
+    Pattern(String),
+    // 无法解析的指针链
+    Chain(ParseChainError),
+    // 指针链无法在进程中解析
+    Resolve(ResolveError),
 }
 
 impl From<vmmap::Error> for Error {
@@ -10,6 +36,18 @@ impl From<vmmap::Error> for Error {
     }
 }
 
+impl From<ParseChainError> for Error {
+    fn from(value: ParseChainError) -> Self {
+        Self::Chain(value)
+    }
+}
+
+impl From<ResolveError> for Error {
+    fn from(value: ResolveError) -> Self {
+        Self::Resolve(value)
+    }
+}
+
 impl From<std::io::Error> for Error {
     fn from(value: std::io::Error) -> Self {
         Self::Io(value)
@@ -21,6 +59,18 @@ impl std::fmt::Display for Error {
         match self {
             Error::Vm(err) => write!(f, "{err}"),
             Error::Io(err) => write!(f, "{err}"),
+            Error::Version(v) => write!(f, "unsupported pointer map version: {v}"),
+            Error::PointerWidth(w) => write!(f, "unsupported pointer width: {w}"),
+            Error::Align(a) => write!(f, "unsupported pointer alignment: {a}"),
+            Error::Truncated => write!(f, "pointer map is truncated"),
+            Error::Checksum => write!(f, "pointer map checksum mismatch"),
+            Error::Corrupted => write!(f, "pointer map is corrupted"),
+            Error::Mismatch => write!(f, "modules info does not match pointer map"),
+            Error::ModuleBase(base) => write!(f, "pointer map module offsets are relative to the {base} base"),
+            Error::Cancelled => write!(f, "operation cancelled"),
+            Error::Pattern(s) => write!(f, "invalid offset pattern: {s}"),
+            Error::Chain(err) => write!(f, "invalid pointer chain: {err}"),
+            Error::Resolve(err) => write!(f, "{err}"),
         }
     }
 }
@@ -0,0 +1,358 @@
+use core::ops::Range;
+use std::{
+    io::{self, Read, Seek, SeekFrom, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::{
+    error::{Error, Result},
+    layout::{Endian, Layout},
+    modules::ModuleBase,
+};
+
+// 指针文件格式，所有头部字段均为小端序
+//
+// magic[8] version:u32 width:u8 endian:u8 align:u8 module_base:u8
+// pid:u64 timestamp:u64 count:u64 checksum:u64
+// app_path_len:u32 app_path modules_len:u32 [start:u64 end:u64 name_len:u32
+// name] [address value] * count
+//
+// checksum 覆盖固定头部之后的全部数据
+// module_base 0 为第一个可读写区域，1 为加载地址，旧文件中该字节为 0
+pub const MAGIC: [u8; 8] = *b"PTRSXMAP";
+pub const VERSION: u32 = 1;
+
+const COUNT_OFFSET: u64 = 32;
+const MAX_STRING_LEN: usize = 0x10000;
+
+#[derive(Debug, Clone)]
+pub struct Header {
+    pub version: u32,
+    pub width: u8,
+    pub endian: Endian,
+    pub align: u8,
+    pub module_base: ModuleBase,
+    pub pid: u64,
+    pub timestamp: u64,
+    pub count: u64,
+    pub checksum: u64,
+    pub app_path: String,
+    pub modules: Vec<(Range<usize>, String)>,
+}
+
+impl Header {
+    pub fn new(
+        pid: u64,
+        app_path: String,
+        modules: Vec<(Range<usize>, String)>,
+        layout: Layout,
+        align: usize,
+        module_base: ModuleBase,
+    ) -> Self {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        Self {
+            version: VERSION,
+            width: layout.width() as u8,
+            endian: layout.endian(),
+            align: align as u8,
+            module_base,
+            pid,
+            timestamp,
+            count: 0,
+            checksum: 0,
+            app_path,
+            modules,
+        }
+    }
+
+    pub fn layout(&self) -> Result<Layout> {
+        Layout::new(self.width as usize, self.endian)
+    }
+
+    fn write_fixed<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let endian = match self.endian {
+            Endian::Little => 0,
+            Endian::Big => 1,
+        };
+        w.write_all(&MAGIC)?;
+        w.write_all(&self.version.to_le_bytes())?;
+        let module_base = match self.module_base {
+            ModuleBase::Writable => 0,
+            ModuleBase::Load => 1,
+        };
+        w.write_all(&[self.width, endian, self.align, module_base])?;
+        w.write_all(&self.pid.to_le_bytes())?;
+        w.write_all(&self.timestamp.to_le_bytes())?;
+        w.write_all(&self.count.to_le_bytes())?;
+        w.write_all(&self.checksum.to_le_bytes())
+    }
+
+    fn write_body<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        write_bytes(w, self.app_path.as_bytes())?;
+        w.write_all(&(self.modules.len() as u32).to_le_bytes())?;
+        self.modules.iter().try_for_each(|(Range { start, end }, name)| {
+            w.write_all(&(*start as u64).to_le_bytes())?;
+            w.write_all(&(*end as u64).to_le_bytes())?;
+            write_bytes(w, name.as_bytes())
+        })
+    }
+
+    // 读取魔数之后的头部，返回的 reader 可以继续读取指针数据并计算校验和
+    pub fn read_after_magic<R: Read>(r: R) -> Result<(Self, ChecksumReader<R>)> {
+        let mut r = r;
+        let version = read_u32(&mut r)?;
+        if version != VERSION {
+            return Err(Error::Version(version));
+        }
+        let mut buf = [0; 4];
+        read_exact(&mut r, &mut buf)?;
+        let [width, endian, align, module_base] = buf;
+        let endian = match endian {
+            0 => Endian::Little,
+            1 => Endian::Big,
+            _ => return Err(Error::Corrupted),
+        };
+        let module_base = match module_base {
+            0 => ModuleBase::Writable,
+            1 => ModuleBase::Load,
+            _ => return Err(Error::Corrupted),
+        };
+        let pid = read_u64(&mut r)?;
+        let timestamp = read_u64(&mut r)?;
+        let count = read_u64(&mut r)?;
+        let checksum = read_u64(&mut r)?;
+
+        let mut r = ChecksumReader::new(r);
+        let app_path = read_string(&mut r)?;
+        let len = read_u32(&mut r)?;
+        let modules = (0..len)
+            .map(|_| {
+                let start = read_u64(&mut r)? as usize;
+                let end = read_u64(&mut r)? as usize;
+                let name = read_string(&mut r)?;
+                Ok((start..end, name))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let header = Self {
+            version,
+            width,
+            endian,
+            align,
+            module_base,
+            pid,
+            timestamp,
+            count,
+            checksum,
+            app_path,
+            modules,
+        };
+        Ok((header, r))
+    }
+}
+
+// 写入指针文件，先写入头部，结束时回填数量和校验和
+pub struct PointerMapWriter<W: Write + Seek> {
+    inner: ChecksumWriter<W>,
+    header: Header,
+    layout: Layout,
+}
+
+impl<W: Write + Seek> PointerMapWriter<W> {
+    pub fn new(w: W, header: Header) -> Result<Self> {
+        let layout = header.layout()?;
+        let mut w = w;
+        header.write_fixed(&mut w)?;
+        let mut inner = ChecksumWriter::new(w);
+        header.write_body(&mut inner)?;
+        Ok(Self { inner, header, layout })
+    }
+
+    #[inline]
+    pub fn write_pair(&mut self, addr: usize, value: usize) -> io::Result<()> {
+        self.header.count += 1;
+        self.layout
+            .write(&mut self.inner, addr)
+            .and(self.layout.write(&mut self.inner, value))
+    }
+
+    pub fn finish(self) -> Result<W> {
+        let Self { inner, header, .. } = self;
+        let checksum = inner.hash;
+        let mut w = inner.inner;
+        w.seek(SeekFrom::Start(COUNT_OFFSET))?;
+        w.write_all(&header.count.to_le_bytes())?;
+        w.write_all(&checksum.to_le_bytes())?;
+        w.seek(SeekFrom::End(0))?;
+        w.flush()?;
+        Ok(w)
+    }
+}
+
+// FNV-1a
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+#[inline]
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(hash, |hash, &b| (hash ^ b as u64).wrapping_mul(FNV_PRIME))
+}
+
+pub struct ChecksumReader<R> {
+    inner: R,
+    hash: u64,
+}
+
+impl<R> ChecksumReader<R> {
+    fn new(inner: R) -> Self {
+        Self { inner, hash: FNV_OFFSET }
+    }
+
+    pub const fn checksum(&self) -> u64 {
+        self.hash
+    }
+}
+
+impl<R: Read> Read for ChecksumReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let size = self.inner.read(buf)?;
+        self.hash = fnv1a(self.hash, &buf[..size]);
+        Ok(size)
+    }
+}
+
+struct ChecksumWriter<W> {
+    inner: W,
+    hash: u64,
+}
+
+impl<W> ChecksumWriter<W> {
+    fn new(inner: W) -> Self {
+        Self { inner, hash: FNV_OFFSET }
+    }
+}
+
+impl<W: Write> Write for ChecksumWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let size = self.inner.write(buf)?;
+        self.hash = fnv1a(self.hash, &buf[..size]);
+        Ok(size)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+// 尽可能填满 buf，返回实际读取的长度，只有到达文件末尾时才会小于 buf 长度
+pub fn read_full<R: Read>(r: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut pos = 0;
+    while pos < buf.len() {
+        match r.read(&mut buf[pos..]) {
+            Ok(0) => break,
+            Ok(n) => pos += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(pos)
+}
+
+fn read_exact<R: Read>(r: &mut R, buf: &mut [u8]) -> Result<()> {
+    match read_full(r, buf)? == buf.len() {
+        true => Ok(()),
+        false => Err(Error::Truncated),
+    }
+}
+
+fn read_u32<R: Read>(r: &mut R) -> Result<u32> {
+    let mut buf = [0; 4];
+    read_exact(r, &mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64<R: Read>(r: &mut R) -> Result<u64> {
+    let mut buf = [0; 8];
+    read_exact(r, &mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_string<R: Read>(r: &mut R) -> Result<String> {
+    let len = read_u32(r)? as usize;
+    if len > MAX_STRING_LEN {
+        return Err(Error::Corrupted);
+    }
+    let mut buf = vec![0; len];
+    read_exact(r, &mut buf)?;
+    String::from_utf8(buf).map_err(|_| Error::Corrupted)
+}
+
+fn write_bytes<W: Write>(w: &mut W, bytes: &[u8]) -> io::Result<()> {
+    w.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    w.write_all(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    use super::*;
+
+    fn write_map() -> Vec<u8> {
+        let modules = vec![
+            (0x1000..0x3000, "app".to_string()),
+            (0x7000..0x8000, "libc.so.6".to_string()),
+        ];
+        let layout = Layout::new(4, Endian::Big).unwrap();
+        let header = Header::new(42, "/bin/app".into(), modules, layout, 4, ModuleBase::Load);
+        let mut writer = PointerMapWriter::new(Cursor::new(Vec::new()), header).unwrap();
+        writer.write_pair(0x1010, 0x7008).unwrap();
+        writer.write_pair(0x7008, 0x2000).unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn read_map(bytes: &[u8]) -> Result<(Header, Vec<u8>)> {
+        assert_eq!(bytes[..8], MAGIC);
+        let (header, mut r) = Header::read_after_magic(&bytes[8..])?;
+        let mut pairs = Vec::new();
+        r.read_to_end(&mut pairs)?;
+        match r.checksum() == header.checksum {
+            true => Ok((header, pairs)),
+            false => Err(Error::Checksum),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let (header, pairs) = read_map(&write_map()).unwrap();
+        assert_eq!(header.version, VERSION);
+        assert_eq!((header.width, header.endian, header.align), (4, Endian::Big, 4));
+        assert_eq!(header.module_base, ModuleBase::Load);
+        assert_eq!((header.pid, header.count), (42, 2));
+        assert_eq!(header.app_path, "/bin/app");
+        assert_eq!(header.modules[1], (0x7000..0x8000, "libc.so.6".to_string()));
+        assert_eq!(pairs, [0, 0, 0x10, 0x10, 0, 0, 0x70, 0x08, 0, 0, 0x70, 0x08, 0, 0, 0x20, 0]);
+    }
+
+    #[test]
+    fn checksum_mismatch() {
+        let mut bytes = write_map();
+        *bytes.last_mut().unwrap() ^= 1;
+        assert!(matches!(read_map(&bytes), Err(Error::Checksum)));
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let mut bytes = write_map();
+        bytes[8..12].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        assert!(matches!(read_map(&bytes), Err(Error::Version(v)) if v == VERSION + 1));
+    }
+
+    #[test]
+    fn truncated() {
+        let bytes = write_map();
+        assert!(matches!(read_map(&bytes[..20]), Err(Error::Truncated)));
+    }
+}
@@ -0,0 +1,76 @@
+use core::mem;
+use std::io::{self, Write};
+
+use super::error::{Error, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+impl Endian {
+    #[cfg(target_endian = "little")]
+    pub const NATIVE: Self = Self::Little;
+    #[cfg(target_endian = "big")]
+    pub const NATIVE: Self = Self::Big;
+}
+
+// 目标进程的指针宽度和字节序，可以在 64 位主机上分析 32 位或大端序的目标
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    width: usize,
+    endian: Endian,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self::NATIVE
+    }
+}
+
+impl Layout {
+    pub const NATIVE: Self = Self { width: mem::size_of::<usize>(), endian: Endian::NATIVE };
+
+    // 指针宽度只能是 4 或 8，并且不能大于主机的指针宽度
+    pub fn new(width: usize, endian: Endian) -> Result<Self> {
+        match width {
+            4 | 8 if width <= mem::size_of::<usize>() => Ok(Self { width, endian }),
+            _ => Err(Error::PointerWidth(width)),
+        }
+    }
+
+    #[inline]
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    #[inline]
+    pub const fn endian(&self) -> Endian {
+        self.endian
+    }
+
+    // bytes 的长度必须等于指针宽度
+    #[inline]
+    pub fn read(&self, bytes: &[u8]) -> usize {
+        let mut buf = [0; mem::size_of::<usize>()];
+        match self.endian {
+            Endian::Little => {
+                buf[..self.width].copy_from_slice(bytes);
+                usize::from_le_bytes(buf)
+            }
+            Endian::Big => {
+                buf[mem::size_of::<usize>() - self.width..].copy_from_slice(bytes);
+                usize::from_be_bytes(buf)
+            }
+        }
+    }
+
+    #[inline]
+    pub fn write<W: Write>(&self, w: &mut W, value: usize) -> io::Result<()> {
+        match self.endian {
+            Endian::Little => w.write_all(&value.to_le_bytes()[..self.width]),
+            Endian::Big => w.write_all(&value.to_be_bytes()[mem::size_of::<usize>() - self.width..]),
+        }
+    }
+}
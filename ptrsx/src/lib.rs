@@ -1,26 +1,53 @@
-use core::{
-    mem,
-    ops::{Bound, ControlFlow, Range},
-};
+use core::ops::{ControlFlow, Range};
 use std::{
-    collections::{BTreeMap, BTreeSet, HashMap},
-    fs::File,
-    io::{BufReader, BufWriter, Cursor, Read, Write},
+    fs::{self, File},
+    io::{BufRead, BufReader, BufWriter, Cursor, Read, Write},
     path::Path,
 };
 
+mod chain_filter;
+mod elf;
 mod error;
+mod header;
+mod layout;
 mod mapping_filter;
+mod module_filter;
+mod modules;
+mod offline;
+mod offset_pattern;
+mod pointer_chain;
+mod pointer_graph;
 mod pointer_map;
 mod pointer_scan;
+mod progress;
 mod rangemap;
+mod resolver;
+mod stability;
 mod try_trait;
 
+pub use chain_filter::{ChainFilter, FilterFn, LastOffset, MaxCount, MinLength, StripCycle};
 pub use error::{Error, Result};
-use mapping_filter::mapping_filter;
+pub use header::Header;
+use header::{read_full, PointerMapWriter, MAGIC};
+pub use layout::{Endian, Layout};
+use mapping_filter::source_filter;
+use module_filter::is_base_module;
+pub use modules::{base_modules, ModuleBase};
+pub use offline::OfflineResolver;
+pub use offset_pattern::{OffsetPattern, OffsetRule};
+pub use pointer_chain::{FieldChain, ParseChainError, PointerChain};
+use pointer_graph::PointerGraph;
 use pointer_map::try_create_pointer_map;
-use pointer_scan::{try_pointer_chain_scan, Chain, Param};
+pub use pointer_scan::Chain;
+use pointer_scan::{
+    try_pointer_chain_scan_fields, try_pointer_chain_scan_multi, try_pointer_chain_scan_par,
+    try_pointer_chain_scan_shortest, Param,
+};
+pub use progress::{Progress, ProgressState};
 use rangemap::RangeMap;
+pub use resolver::{ChainResolver, Region, ResolveError, ResolveStep, ResolveTrace};
+pub use stability::{ChainScore, StabilityScorer};
+pub use try_trait::{FromResidual, Try};
 #[cfg(target_os = "macos")]
 use vmmap::macos::cmd::ProcessInfoCmdFixed as ProcessInfo;
 #[cfg(any(target_os = "linux", target_os = "windows", target_os = "android"))]
@@ -57,11 +84,25 @@ impl<'a> Iterator for ModuleIter<'a> {
     }
 }
 
+// 创建指针文件的参数
+#[derive(Default, Clone)]
+pub struct DumpParam {
+    // 目标进程的指针宽度和字节序
+    pub layout: Layout,
+    // 指针地址对齐，可选 8 4 2 1，默认与指针宽度相同
+    pub align: Option<usize>,
+    // 模块基址的计算方式，决定 module+offset 中 offset 的含义
+    pub module_base: ModuleBase,
+    // 读取进度和取消
+    pub progress: Option<Progress>,
+}
+
 #[derive(Default)]
 pub struct PtrsxScanner {
     index: RangeMap<usize, String>,
-    points: BTreeSet<usize>,
-    map: BTreeMap<usize, Vec<usize>>,
+    graph: PointerGraph,
+    // 已加载指针文件的模块基址计算方式，文本格式的模块信息和旧版本指针文件中没有记录
+    module_base: Option<ModuleBase>,
 }
 
 pub struct UserParam {
@@ -78,1050 +119,675 @@ pub struct UserParam {
     pub max: Option<usize>,
     // 必须以指定偏移结束
     pub last: Option<isize>,
+    // 每层偏移的约束，例如 *.0x18.?.[0x10..0x40]，在扫描时剪枝
+    pub pattern: Option<OffsetPattern>,
+    // 每个偏移都必须是该值的倍数
+    pub offset_align: Option<usize>,
+    // 只使用这些模块作为基址，为空时使用全部模块，支持 * 和 ? 通配符
+    pub include: Vec<String>,
+    // 不使用这些模块作为基址，优先于 include
+    pub exclude: Vec<String>,
+    // 按长度从短到长输出指针链，与 max 一起使用时保留最短的指针链
+    pub shortest: bool,
+    // 单个目标地址扫描使用的线程数，None 为单线程，结果顺序与单线程相同
+    pub threads: Option<usize>,
+    // 读取进度和取消
+    pub progress: Option<Progress>,
 }
 
 impl PtrsxScanner {
-    pub fn create_pointer_map<P1, P2, P3>(&self, proc: &P1, path1: P2, path2: P3) -> Result<()>
+    pub fn create_pointer_map<P1, P2, P3>(&self, proc: &P1, param: DumpParam, path1: P2, path2: P3) -> Result<()>
     where
         P1: ProcessInfo + VirtualMemoryRead,
         P2: AsRef<Path>,
         P3: AsRef<Path>,
     {
+        let DumpParam { layout, align, module_base, progress } = param;
+        let progress = progress.unwrap_or_default();
+        let align = align.unwrap_or(layout.width());
+        if !matches!(align, 1 | 2 | 4 | 8) {
+            return Err(Error::Align(align));
+        }
+
         // 获取全部内存区域
-        let iter = proc.get_maps().collect::<Result<Vec<_>, vmmap::Error>>()?.into_iter();
-        let vqs = iter
+        let maps = proc.get_maps().collect::<Result<Vec<_>, vmmap::Error>>()?;
+        let modules = base_modules(proc, &maps, module_base);
+        let vqs = maps
+            .into_iter()
             .filter(|x| x.is_read() && x.is_write())
-            .filter(mapping_filter)
+            .filter(|x| source_filter(proc, x))
             .collect::<Vec<_>>();
 
+        // 将处理过的内存映射信息写入文件
+        let (path1, path2) = (path1.as_ref(), path2.as_ref());
         let file = File::options().append(true).create_new(true).open(path1)?;
         let mut writer = BufWriter::new(file);
+        modules
+            .iter()
+            .try_for_each(|(Range { start, end }, name)| writeln!(writer, "{start:x}-{end:x} {name}"))?;
+        writer.flush()?;
 
-        // 处理所有可用于基址的模块，合并处于同一模块的区域，截断模块路径只保留模块名，
-        // 检查如果有多个模块名相同但是路径不同的区域就在模块名后面加一个数字，
-        // 将处理过的内存映射信息写入文件
-        let mut counts = HashMap::new();
-        vqs.iter()
-            .flat_map(|x| Some(Module { start: x.start(), end: x.end(), name: x.name()? }))
-            .fold(Vec::<Module>::with_capacity(vqs.len()), |mut acc, cur| {
-                match acc.last_mut() {
-                    Some(last) if last.name == cur.name => last.end = cur.end,
-                    _ => acc.push(cur),
-                }
-                acc
-            })
-            .into_iter()
-            .try_for_each(|Module { start, end, name }| {
-                let name = Path::new(name).file_name().and_then(|s| s.to_str()).unwrap_or(name);
-                let count = counts.entry(name).or_insert(0);
-                let name = format!("{name}[{count}]");
-                *count += 1;
-                writeln!(writer, "{start:x}-{end:x} {name}")
-            })?;
-
-        let file = File::options().append(true).create_new(true).open(path2)?;
-        let mut writer = BufWriter::new(file);
+        let file = File::options().write(true).create_new(true).open(path2)?;
+        let app_path = proc.app_path().to_string_lossy().into_owned();
+        let header = Header::new(proc.pid() as u64, app_path, modules, layout, align, module_base);
+        let mut writer = PointerMapWriter::new(BufWriter::new(file), header)?;
 
         // 将 [k=地址:v=k中所储存的指针] 数据写入文件
-        let mut f = |k: usize, v: usize| {
-            writer
-                .write_all(&k.to_ne_bytes())
-                .and(writer.write_all(&v.to_ne_bytes()))
-        };
-        try_create_pointer_map(proc, &vqs, true, &mut f)?;
+        let mut f = |k: usize, v: usize| writer.write_pair(k, v);
+        try_create_pointer_map(proc, &vqs, align, layout, &progress, &mut f)?;
+
+        // 取消时删除不完整的文件
+        if progress.is_cancelled() {
+            drop(writer);
+            let _ = fs::remove_file(path1);
+            let _ = fs::remove_file(path2);
+            return Err(Error::Cancelled);
+        }
+        writer.finish()?;
 
         Ok(())
     }
 
+    // 同时支持带头部的指针文件和旧版本没有头部的指针文件
     pub fn load_pointer_map<R: Read>(&mut self, reader: R) -> Result<()> {
-        const BUF_SIZE: usize = mem::size_of::<usize>() * 0x20000;
-        const CHUNK_SIZE: usize = mem::size_of::<usize>() * 2;
-        let mut buf = vec![0; BUF_SIZE];
-        let mut cursor = Cursor::new(reader);
+        let mut reader = reader;
+        let mut magic = [0; 8];
+        let size = read_full(&mut reader, &mut magic)?;
+        if size != magic.len() || magic != MAGIC {
+            self.graph = self.read_pairs(&mut Cursor::new(&magic[..size]).chain(reader), Layout::NATIVE, None)?;
+            return Ok(());
+        }
+
+        // 校验通过之前不修改已加载的内容
+        let (header, mut reader) = Header::read_after_magic(reader)?;
+        let layout = header.layout()?;
+        self.check_header(&header)?;
+        let graph = self.read_pairs(&mut reader, layout, Some(header.count))?;
+        if reader.checksum() != header.checksum {
+            return Err(Error::Checksum);
+        }
+        self.merge_header(header)?;
+        self.graph = graph;
+        Ok(())
+    }
+
+    // 加载带头部的指针文件，存在匹配的索引文件 <path>.idx 时直接映射到内存，
+    // 否则读取指针文件构建索引并保存，下次加载时使用
+    // 没有头部的旧版本指针文件无法判断索引是否过期，总是重新读取
+    pub fn load_pointer_map_file<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut magic = [0; 8];
+        let size = read_full(&mut reader, &mut magic)?;
+        if size != magic.len() || magic != MAGIC {
+            return self.load_pointer_map(File::open(path)?);
+        }
+        let (header, _) = Header::read_after_magic(reader)?;
+
+        let mut idx_path = path.as_os_str().to_owned();
+        idx_path.push(".idx");
+        // 已经加载过其他指针文件时合并后的索引与该文件不对应，不读取也不保存
+        if !self.graph.is_empty() {
+            return self.load_pointer_map(File::open(path)?);
+        }
+        if let Ok((graph, source)) = PointerGraph::open(&idx_path) {
+            if source == header.checksum {
+                self.merge_header(header)?;
+                self.graph = graph;
+                return Ok(());
+            }
+        }
+
+        self.load_pointer_map(File::open(path)?)?;
+        // 索引只是缓存，目录不可写时也不影响加载
+        let _ = self.graph.save(idx_path, header.checksum);
+        Ok(())
+    }
+
+    // 已加载指针文件的模块基址计算方式，未知时为 None
+    pub fn module_base(&self) -> Option<ModuleBase> {
+        self.module_base
+    }
+
+    // 检查已加载的指针文件与 base 使用相同的模块基址计算方式，未知时视为相同
+    pub fn check_module_base(&self, base: ModuleBase) -> Result<()> {
+        match self.module_base {
+            Some(found) if found != base => Err(Error::ModuleBase(found)),
+            _ => Ok(()),
+        }
+    }
+
+    // 检查指针文件头部的模块信息和基址计算方式与已加载的内容一致
+    fn check_header(&self, header: &Header) -> Result<()> {
+        self.check_module_base(header.module_base)?;
+        if !self.index.is_empty()
+            && !self
+                .index
+                .iter()
+                .map(|(range, name)| (range, name.as_str()))
+                .eq(header.modules.iter().map(|(range, name)| (range, name.as_str())))
+        {
+            return Err(Error::Mismatch);
+        }
+        Ok(())
+    }
+
+    // 检查全部通过后才修改，失败时已加载的内容不变
+    fn merge_header(&mut self, header: Header) -> Result<()> {
+        self.check_header(&header)?;
+        self.module_base = Some(header.module_base);
+        if self.index.is_empty() {
+            self.index.extend(header.modules);
+        }
+        Ok(())
+    }
+
+    // 返回合并了已加载指针的新索引，不修改 self
+    fn read_pairs<R: Read>(&self, reader: &mut R, layout: Layout, count: Option<u64>) -> Result<PointerGraph> {
+        let chunk_size = layout.width() * 2;
+        let mut buf = vec![0; chunk_size * 0x10000];
+        let (mut points, mut targets) = (Vec::new(), Vec::new());
         loop {
-            let size = cursor.get_mut().read(&mut buf)?;
+            let size = read_full(reader, &mut buf)?;
             if size == 0 {
                 break;
             }
-            for chuks in buf[..size].chunks_exact(CHUNK_SIZE) {
-                let (key, value) = chuks.split_at(mem::size_of::<usize>());
-                let (key, value) =
-                    (usize::from_ne_bytes(key.try_into().unwrap()), usize::from_ne_bytes(value.try_into().unwrap()));
-                if self.points.insert(key) {
-                    self.map.entry(value).or_default().push(key);
-                }
+            for chuks in buf[..size].chunks_exact(chunk_size) {
+                let (key, value) = chuks.split_at(layout.width());
+                points.push(layout.read(key));
+                targets.push(layout.read(value));
+            }
+            if size % chunk_size != 0 {
+                return Err(Error::Truncated);
             }
         }
-        Ok(())
+        let total = points.len() as u64;
+        match count {
+            Some(count) if count > total => return Err(Error::Truncated),
+            Some(count) if count < total => return Err(Error::Corrupted),
+            _ => {}
+        }
+        drop(buf);
+        // 已经加载的指针优先
+        Ok(self.graph.merge(points, targets))
     }
 
+    // 同时支持文本格式的模块信息和带头部的指针文件
     pub fn load_modules_info<R: Read>(&mut self, r: R) -> Result<()> {
-        let contents = &mut String::with_capacity(0x80000);
         let mut reader = BufReader::new(r);
-        let _ = reader.read_to_string(contents)?;
+        let mut magic = [0; 8];
+        let size = read_full(&mut reader, &mut magic)?;
+        if size == magic.len() && magic == MAGIC {
+            let (header, _) = Header::read_after_magic(reader)?;
+            return self.merge_header(header);
+        }
+
+        let contents = &mut String::with_capacity(0x80000);
+        let _ = Cursor::new(&magic[..size]).chain(reader).read_to_string(contents)?;
         self.index
             .extend(ModuleIter::new(contents).map(|Module { start, end, name }| (start..end, name.to_string())));
         Ok(())
     }
 
     pub fn pointer_chain_scanner(&self, param: UserParam, path: impl AsRef<Path>) -> Result<()> {
+        self.pointer_chain_scanner_with(param, (), path)
+    }
+
+    // 额外的过滤器在内置的 node/last/use_cycle 之后，max 之前执行
+    // 取消时保留已经写入的结果并返回 Error::Cancelled
+    pub fn pointer_chain_scanner_with<F>(&self, param: UserParam, filter: F, path: impl AsRef<Path>) -> Result<()>
+    where
+        F: ChainFilter,
+    {
+        let progress = param.progress.clone().unwrap_or_default();
+        let param = UserParam { progress: Some(progress.clone()), ..param };
         let file = File::options().append(true).create_new(true).open(path)?;
         let mut writer = BufWriter::new(file);
+        let mut f = |chain: &PointerChain| writeln!(writer, "{chain}");
+        self.try_pointer_chain_scanner(param, filter, &mut f)?;
+        writer.flush()?;
+        match progress.is_cancelled() {
+            true => Err(Error::Cancelled),
+            false => Ok(()),
+        }
+    }
 
-        let points = &self
-            .index
-            .iter()
-            .flat_map(|(Range { start, end }, _)| self.points.range((Bound::Included(start), Bound::Included(end))))
-            .copied()
-            .collect::<Vec<_>>();
+    // 将每条指针链交给回调处理，不写入文件
+    // 额外的过滤器在内置的 node/last/use_cycle 之后，max 之前执行
+    // 取消时提前正常返回，需要调用者检查 progress 是否已取消
+    pub fn try_pointer_chain_scanner<C, F, R>(&self, param: UserParam, filter: C, f: &mut F) -> R
+    where
+        C: ChainFilter,
+        F: FnMut(&PointerChain) -> R,
+        R: Try<Output = ()>,
+    {
+        let points = &self.base_points(&param.include, &param.exclude);
+
+        let UserParam {
+            depth,
+            addr,
+            range,
+            use_module,
+            use_cycle,
+            node,
+            max,
+            last,
+            pattern,
+            offset_align,
+            shortest,
+            threads,
+            progress,
+            ..
+        } = param;
+        let progress = progress.unwrap_or_default();
+        let pattern = pattern.unwrap_or_default();
+        let align = offset_align.unwrap_or(1);
+        let param = Param { depth, min_depth: 0, addr, range, pattern: &pattern, align };
+
+        let mut filter = ((node.map(MinLength), last.map(LastOffset)), (use_cycle.then_some(StripCycle), filter));
+        // 只计算成功转换为模块名+偏移的指针链
+        let mut count = max.map(MaxCount::new);
+
+        // 复用同一个缓冲区，避免每条指针链都重新分配
+        let mut buf = PointerChain::default();
+        let mut g = |chain: Chain| {
+            let chain = match filter.filter(chain) {
+                ControlFlow::Continue(Some(chain)) => chain,
+                ControlFlow::Continue(None) => return ControlFlow::Continue(()),
+                ControlFlow::Break(_) => return ControlFlow::Break(R::from_output(())),
+            };
+            if !self.fill_pointer_chain(&chain, use_module, &mut buf) {
+                return ControlFlow::Continue(());
+            }
+            if let ControlFlow::Break(_) = ChainFilter::filter(&mut count, chain) {
+                return ControlFlow::Break(R::from_output(()));
+            }
+            progress.add_chain();
+            match Try::branch(f(&buf)) {
+                ControlFlow::Continue(c) => ControlFlow::Continue(c),
+                ControlFlow::Break(b) => ControlFlow::Break(FromResidual::from_residual(b)),
+            }
+        };
 
-        let UserParam { depth, addr, range, use_module, use_cycle, node, max, last } = param;
-        let param = Param { depth, addr, range };
+        let threads = threads.unwrap_or(1);
+        let result = match shortest {
+            // 长度小于 node 的指针链会被过滤，不需要单独扫描
+            true => {
+                let param = Param { min_depth: node.unwrap_or(0).min(depth), ..param };
+                try_pointer_chain_scan_shortest(&self.graph, points, param, threads, &progress, &mut g)
+            }
+            false => try_pointer_chain_scan_par(&self.graph, points, param, threads, &progress, &mut g),
+        };
+        match result {
+            ControlFlow::Continue(_) => R::from_output(()),
+            ControlFlow::Break(b) => b,
+        }
+    }
 
-        match (use_module, use_cycle) {
-            (true, true) => match (node, max, last) {
-                (None, None, None) => {
-                    let mut f = |chain: Chain| {
-                        let addr = chain.addr();
-                        let Some((Range { start, .. }, name)) = self.index.get_key_value(addr) else {
-                            return ControlFlow::Continue(());
-                        };
+    // 多个目标地址共享同一次遍历，所有结果写入同一个文件，每行以目标地址开头
+    pub fn pointer_chain_scanner_multi(&self, param: UserParam, addrs: &[usize], path: impl AsRef<Path>) -> Result<()> {
+        let progress = param.progress.clone().unwrap_or_default();
+        let param = UserParam { progress: Some(progress.clone()), ..param };
+        let file = File::options().append(true).create_new(true).open(path)?;
+        let mut writer = BufWriter::new(file);
+        let mut f = |i: usize, chain: &PointerChain| writeln!(writer, "{:x} {chain}", addrs[i]);
+        self.try_pointer_chain_scanner_multi(param, addrs, (), &mut f)?;
+        writer.flush()?;
+        match progress.is_cancelled() {
+            true => Err(Error::Cancelled),
+            false => Ok(()),
+        }
+    }
 
-                        match chain.ref_cycle() {
-                            Some(mut iter) => match write!(writer, "{name}+{}", addr - start)
-                                .and(iter.try_for_each(|o| write!(writer, ".{o}")))
-                                .and(writeln!(writer))
-                            {
-                                Ok(_) => ControlFlow::Continue(()),
-                                Err(err) => ControlFlow::Break(Err(err)),
-                            },
+    // 多个目标地址共享同一次遍历，使用 addrs 代替 param.addr
+    // f 的第一个参数是目标地址在 addrs 中的序号，每个目标地址的结果与单独扫描时相同
+    // max 对每个目标地址单独计算，额外的过滤器由所有目标地址共享，
+    // 在内置的 node/last/use_cycle 之后，max 之前执行
+    pub fn try_pointer_chain_scanner_multi<C, F, R>(&self, param: UserParam, addrs: &[usize], filter: C, f: &mut F) -> R
+    where
+        C: ChainFilter,
+        F: FnMut(usize, &PointerChain) -> R,
+        R: Try<Output = ()>,
+    {
+        let points = &self.base_points(&param.include, &param.exclude);
+
+        let UserParam {
+            depth,
+            range,
+            use_module,
+            use_cycle,
+            node,
+            max,
+            last,
+            pattern,
+            offset_align,
+            shortest,
+            threads,
+            progress,
+            ..
+        } = param;
+        let progress = progress.unwrap_or_default();
+        let pattern = pattern.unwrap_or_default();
+        let align = offset_align.unwrap_or(1);
+
+        let mut filters = addrs
+            .iter()
+            .map(|_| ((node.map(MinLength), last.map(LastOffset)), max.map(MaxCount::new)))
+            .collect::<Vec<_>>();
+        let mut filter = (use_cycle.then_some(StripCycle), filter);
+        let mut done = vec![false; addrs.len()];
+        let mut remain = addrs.len();
+
+        let mut buf = PointerChain::default();
+        let mut g = |i: usize, chain: Chain| {
+            if done[i] {
+                return ControlFlow::Continue(());
+            }
+            let (pre, count) = &mut filters[i];
+            let chain = match pre.filter(chain) {
+                ControlFlow::Continue(Some(chain)) => chain,
+                _ => return ControlFlow::Continue(()),
+            };
+            let chain = match filter.filter(chain) {
+                ControlFlow::Continue(Some(chain)) => chain,
+                ControlFlow::Continue(None) => return ControlFlow::Continue(()),
+                ControlFlow::Break(_) => return ControlFlow::Break(R::from_output(())),
+            };
+            if !self.fill_pointer_chain(&chain, use_module, &mut buf) {
+                return ControlFlow::Continue(());
+            }
+            if let ControlFlow::Break(_) = ChainFilter::filter(count, chain) {
+                return ControlFlow::Continue(());
+            }
+            // 达到 max 的目标地址不再输出，全部达到后结束扫描
+            if count.as_ref().zip(max).is_some_and(|(c, m)| c.count() >= m) {
+                done[i] = true;
+                remain -= 1;
+            }
+            progress.add_chain();
+            if let ControlFlow::Break(b) = Try::branch(f(i, &buf)) {
+                return ControlFlow::Break(FromResidual::from_residual(b));
+            }
+            match remain {
+                0 => ControlFlow::Break(R::from_output(())),
+                _ => ControlFlow::Continue(()),
+            }
+        };
 
-                            None => match write!(writer, "{name}+{}", addr - start)
-                                .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                .and(writeln!(writer))
-                            {
-                                Ok(_) => ControlFlow::Continue(()),
-                                Err(err) => ControlFlow::Break(Err(err)),
-                            },
-                        }
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (None, None, Some(last)) => {
-                    let mut f = |chain: Chain| {
-                        if chain.last().is_some_and(|o| last.eq(o)) {
-                            let addr = chain.addr();
-                            let Some((Range { start, .. }, name)) = self.index.get_key_value(addr) else {
-                                return ControlFlow::Continue(());
-                            };
+        // 长度小于 node 的指针链会被过滤，不需要单独扫描
+        let levels = match shortest {
+            true => (node.unwrap_or(0).min(depth)..=depth).map(|d| (d, d)).collect(),
+            false => vec![(depth, 0)],
+        };
+        let threads = threads.unwrap_or(1);
+        for (depth, min_depth) in levels {
+            let param = Param { depth, min_depth, addr: 0, range, pattern: &pattern, align };
+            match try_pointer_chain_scan_multi(&self.graph, points, param, addrs, threads, &progress, &mut g) {
+                ControlFlow::Continue(_) => {}
+                ControlFlow::Break(b) => return b,
+            }
+            if progress.is_cancelled() {
+                break;
+            }
+        }
+        R::from_output(())
+    }
 
-                            return match chain.ref_cycle() {
-                                Some(mut iter) => match write!(writer, "{name}+{}", addr - start)
-                                    .and(iter.try_for_each(|o| write!(writer, ".{o}")))
-                                    .and(writeln!(writer))
-                                {
-                                    Ok(_) => ControlFlow::Continue(()),
-                                    Err(err) => ControlFlow::Break(Err(err)),
-                                },
+    // 关联扫描，所有结果写入同一个文件
+    pub fn pointer_chain_scanner_fields(
+        &self,
+        param: UserParam,
+        addrs: &[usize],
+        path: impl AsRef<Path>,
+    ) -> Result<()> {
+        let progress = param.progress.clone().unwrap_or_default();
+        let param = UserParam { progress: Some(progress.clone()), ..param };
+        let file = File::options().append(true).create_new(true).open(path)?;
+        let mut writer = BufWriter::new(file);
+        let mut f = |chain: &FieldChain| writeln!(writer, "{chain}");
+        self.try_pointer_chain_scanner_fields(param, addrs, (), &mut f)?;
+        writer.flush()?;
+        match progress.is_cancelled() {
+            true => Err(Error::Cancelled),
+            false => Ok(()),
+        }
+    }
 
-                                None => match write!(writer, "{name}+{}", addr - start)
-                                    .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                    .and(writeln!(writer))
-                                {
-                                    Ok(_) => ControlFlow::Continue(()),
-                                    Err(err) => ControlFlow::Break(Err(err)),
-                                },
-                            };
-                        }
-                        ControlFlow::Continue(())
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (None, Some(max), None) => {
-                    let mut n = 0;
-                    let mut f = |chain: Chain| {
-                        if n >= max {
-                            return ControlFlow::Break(Ok(()));
-                        }
-                        let Some((Range { start, .. }, name)) = self.index.get_key_value(addr) else {
-                            return ControlFlow::Continue(());
-                        };
+    // 关联扫描同一个对象的多个字段，使用 addrs 代替 param.addr
+    // 只输出解引用后能够通过不同的最后一个偏移到达每个目标地址的前缀
+    // last 对关联扫描没有意义，会被忽略，node 包含最后一个偏移
+    pub fn try_pointer_chain_scanner_fields<C, F, R>(
+        &self,
+        param: UserParam,
+        addrs: &[usize],
+        filter: C,
+        f: &mut F,
+    ) -> R
+    where
+        C: ChainFilter,
+        F: FnMut(&FieldChain) -> R,
+        R: Try<Output = ()>,
+    {
+        let points = &self.base_points(&param.include, &param.exclude);
+
+        let UserParam {
+            depth,
+            range,
+            use_module,
+            use_cycle,
+            node,
+            max,
+            pattern,
+            offset_align,
+            shortest,
+            threads,
+            progress,
+            ..
+        } = param;
+        let progress = progress.unwrap_or_default();
+        let pattern = pattern.unwrap_or_default();
+        let align = offset_align.unwrap_or(1);
+
+        let mut filter = ((node.map(MinLength), use_cycle.then_some(StripCycle)), filter);
+        let mut count = max.map(MaxCount::new);
+
+        let mut buf = FieldChain::default();
+        let mut g = |chain: Chain| {
+            let chain = match filter.filter(chain) {
+                ControlFlow::Continue(Some(chain)) => chain,
+                ControlFlow::Continue(None) => return ControlFlow::Continue(()),
+                ControlFlow::Break(_) => return ControlFlow::Break(R::from_output(())),
+            };
+            let Some(&last) = chain.last() else {
+                return ControlFlow::Continue(());
+            };
+            if !self.fill_pointer_chain(&chain, use_module, &mut buf.prefix) {
+                return ControlFlow::Continue(());
+            }
+            if let ControlFlow::Break(_) = ChainFilter::filter(&mut count, chain) {
+                return ControlFlow::Break(R::from_output(()));
+            }
+            // 最后一个偏移指向 addrs[0]，其他字段的偏移由目标地址之间的距离得到
+            buf.prefix.offsets.pop();
+            buf.fields.clear();
+            buf.fields.extend(
+                addrs
+                    .iter()
+                    .map(|a| last.wrapping_add(a.wrapping_sub(addrs[0]) as isize)),
+            );
+            progress.add_chain();
+            match Try::branch(f(&buf)) {
+                ControlFlow::Continue(c) => ControlFlow::Continue(c),
+                ControlFlow::Break(b) => ControlFlow::Break(FromResidual::from_residual(b)),
+            }
+        };
 
-                        match chain.ref_cycle() {
-                            Some(mut iter) => match write!(writer, "{name}+{}", addr - start)
-                                .and(iter.try_for_each(|o| write!(writer, ".{o}")))
-                                .and(writeln!(writer))
-                            {
-                                Ok(_) => {
-                                    n += 1;
-                                    ControlFlow::Continue(())
-                                }
-                                Err(err) => ControlFlow::Break(Err(err)),
-                            },
-                            None => match write!(writer, "{name}+{}", addr - start)
-                                .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                .and(writeln!(writer))
-                            {
-                                Ok(_) => {
-                                    n += 1;
-                                    ControlFlow::Continue(())
-                                }
-                                Err(err) => ControlFlow::Break(Err(err)),
-                            },
-                        }
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (None, Some(max), Some(last)) => {
-                    let mut n = 0;
-                    let mut f = |chain: Chain| {
-                        if n >= max {
-                            return ControlFlow::Break(Ok(()));
-                        }
-                        if chain.last().is_some_and(|o| last.eq(o)) {
-                            let addr = chain.addr();
-                            let Some((Range { start, .. }, name)) = self.index.get_key_value(addr) else {
-                                return ControlFlow::Continue(());
-                            };
+        // 关联扫描不包含长度为 0 的指针链
+        let levels = match shortest {
+            true => (node.unwrap_or(0).clamp(1, depth.max(1))..=depth)
+                .map(|d| (d, d))
+                .collect(),
+            false => vec![(depth, 0)],
+        };
+        let threads = threads.unwrap_or(1);
+        for (depth, min_depth) in levels {
+            let param = Param { depth, min_depth, addr: 0, range, pattern: &pattern, align };
+            match try_pointer_chain_scan_fields(&self.graph, points, param, addrs, threads, &progress, &mut g) {
+                ControlFlow::Continue(_) => {}
+                ControlFlow::Break(b) => return b,
+            }
+            if progress.is_cancelled() {
+                break;
+            }
+        }
+        R::from_output(())
+    }
 
-                            return match chain.ref_cycle() {
-                                Some(mut iter) => {
-                                    match write!(writer, "{name}+{}", addr - start)
-                                        .and(iter.try_for_each(|o| write!(writer, ".{o}")))
-                                        .and(writeln!(writer))
-                                    {
-                                        Ok(_) => {
-                                            n += 1;
-                                            ControlFlow::Continue(())
-                                        }
-                                        Err(err) => ControlFlow::Break(Err(err)),
-                                    }
-                                }
-                                None => match write!(writer, "{name}+{}", addr - start)
-                                    .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                    .and(writeln!(writer))
-                                {
-                                    Ok(_) => {
-                                        n += 1;
-                                        ControlFlow::Continue(())
-                                    }
-                                    Err(err) => ControlFlow::Break(Err(err)),
-                                },
-                            };
-                        }
-                        ControlFlow::Continue(())
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (Some(node), None, None) => {
-                    let mut f = |chain: Chain| {
-                        if chain.len() >= node {
-                            let addr = chain.addr();
-                            let Some((Range { start, .. }, name)) = self.index.get_key_value(addr) else {
-                                return ControlFlow::Continue(());
-                            };
+    // 位于基址模块中的全部指针地址
+    fn base_points(&self, include: &[String], exclude: &[String]) -> Vec<usize> {
+        let points = self.graph.points();
+        self.index
+            .iter()
+            .filter(|(_, name)| is_base_module(name, include, exclude))
+            .flat_map(|(Range { start, end }, _)| {
+                let lo = points.partition_point(|x| x < start);
+                let hi = points.partition_point(|x| x <= end);
+                &points[lo..hi.max(lo)]
+            })
+            .copied()
+            .collect()
+    }
 
-                            return match chain.ref_cycle() {
-                                Some(mut iter) => match write!(writer, "{name}+{}", addr - start)
-                                    .and(iter.try_for_each(|o| write!(writer, ".{o}")))
-                                    .and(writeln!(writer))
-                                {
-                                    Ok(_) => ControlFlow::Continue(()),
-                                    Err(err) => ControlFlow::Break(Err(err)),
-                                },
-                                None => match write!(writer, "{name}+{}", addr - start)
-                                    .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                    .and(writeln!(writer))
-                                {
-                                    Ok(_) => ControlFlow::Continue(()),
-                                    Err(err) => ControlFlow::Break(Err(err)),
-                                },
-                            };
-                        }
-                        ControlFlow::Continue(())
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (Some(node), None, Some(last)) => {
-                    let mut f = |chain: Chain| {
-                        if chain.len() >= node && chain.last().is_some_and(|o| last.eq(o)) {
-                            let addr = chain.addr();
-                            let Some((Range { start, .. }, name)) = self.index.get_key_value(addr) else {
-                                return ControlFlow::Continue(());
-                            };
+    // 将基址转换为模块名+偏移
+    // use_module 为 true 并且基址不在任何模块中时返回 false
+    fn fill_pointer_chain(&self, chain: &Chain, use_module: bool, buf: &mut PointerChain) -> bool {
+        let addr = chain.addr();
+        match use_module {
+            true => {
+                let Some((Range { start, .. }, name)) = self.index.get_key_value(addr) else {
+                    return false;
+                };
+                let module = buf.module.get_or_insert_with(String::new);
+                module.clear();
+                module.push_str(name);
+                buf.offset = addr - start;
+            }
+            false => {
+                buf.module = None;
+                buf.offset = addr;
+            }
+        }
+        buf.offsets.clear();
+        buf.offsets.extend(chain.data());
+        true
+    }
 
-                            return match chain.ref_cycle() {
-                                Some(mut iter) => match write!(writer, "{name}+{}", addr - start)
-                                    .and(iter.try_for_each(|o| write!(writer, ".{o}")))
-                                    .and(writeln!(writer))
-                                {
-                                    Ok(_) => ControlFlow::Continue(()),
-                                    Err(err) => ControlFlow::Break(Err(err)),
-                                },
-                                None => match write!(writer, "{name}+{}", addr - start)
-                                    .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                    .and(writeln!(writer))
-                                {
-                                    Ok(_) => ControlFlow::Continue(()),
-                                    Err(err) => ControlFlow::Break(Err(err)),
-                                },
-                            };
-                        }
-                        ControlFlow::Continue(())
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (Some(node), Some(max), None) => {
-                    let mut n = 0;
-                    let mut f = |chain: Chain| {
-                        if n >= max {
-                            return ControlFlow::Break(Ok(()));
-                        }
+    // 使用已加载的指针文件离线解析指针链
+    pub fn offline_resolver(&self) -> OfflineResolver<'_> {
+        let modules = self
+            .index
+            .iter()
+            .map(|(range, name)| (name.as_str(), range.start))
+            .collect();
+        OfflineResolver::new(modules, &self.graph)
+    }
 
-                        if chain.len() >= node {
-                            let addr = chain.addr();
-                            let Some((Range { start, .. }, name)) = self.index.get_key_value(addr) else {
-                                return ControlFlow::Continue(());
-                            };
+    // 使用已加载的指针文件验证 reader 中的指针链，
+    // 将解析后等于 addr 的行写入 writer，无法解析的行视为无效
+    // 返回 (读取的指针链数量, 有效的指针链数量)
+    pub fn validate_chains<R, W>(&self, reader: R, addr: usize, writer: W) -> Result<(usize, usize)>
+    where
+        R: BufRead,
+        W: Write,
+    {
+        let resolver = self.offline_resolver();
+        let mut writer = writer;
+        let (mut total, mut valid) = (0, 0);
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            total += 1;
+            let Ok(chain) = line.parse::<PointerChain>() else {
+                continue;
+            };
+            if resolver.resolve(&chain).is_ok_and(|x| x == addr) {
+                writeln!(writer, "{line}")?;
+                valid += 1;
+            }
+        }
+        writer.flush()?;
+        Ok((total, valid))
+    }
 
-                            return match chain.ref_cycle() {
-                                Some(mut iter) => match write!(writer, "{name}+{}", addr - start)
-                                    .and(iter.try_for_each(|o| write!(writer, ".{o}")))
-                                    .and(writeln!(writer))
-                                {
-                                    Ok(_) => {
-                                        n += 1;
-                                        ControlFlow::Continue(())
-                                    }
-                                    Err(err) => ControlFlow::Break(Err(err)),
-                                },
-                                None => match write!(writer, "{name}+{}", addr - start)
-                                    .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                    .and(writeln!(writer))
-                                {
-                                    Ok(_) => {
-                                        n += 1;
-                                        ControlFlow::Continue(())
-                                    }
-                                    Err(err) => ControlFlow::Break(Err(err)),
-                                },
-                            };
-                        }
-                        ControlFlow::Continue(())
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (Some(node), Some(max), Some(last)) => {
-                    let mut n = 0;
-                    let mut f = |chain: Chain| {
-                        if n >= max {
-                            return ControlFlow::Break(Ok(()));
-                        }
-                        if chain.len() >= node && chain.last().is_some_and(|o| last.eq(o)) {
-                            let addr = chain.addr();
-                            let Some((Range { start, .. }, name)) = self.index.get_key_value(addr) else {
-                                return ControlFlow::Continue(());
-                            };
+    pub fn reset(&mut self) {
+        self.index.clear();
+        self.graph = PointerGraph::default();
+        self.module_base = None;
+    }
+}
 
-                            return match chain.ref_cycle() {
-                                Some(mut iter) => match write!(writer, "{name}+{}", addr - start)
-                                    .and(iter.try_for_each(|o| write!(writer, ".{o}")))
-                                    .and(writeln!(writer))
-                                {
-                                    Ok(_) => {
-                                        n += 1;
-                                        ControlFlow::Continue(())
-                                    }
-                                    Err(err) => ControlFlow::Break(Err(err)),
-                                },
-                                None => match write!(writer, "{name}+{}", addr - start)
-                                    .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                    .and(writeln!(writer))
-                                {
-                                    Ok(_) => {
-                                        n += 1;
-                                        ControlFlow::Continue(())
-                                    }
-                                    Err(err) => ControlFlow::Break(Err(err)),
-                                },
-                            };
-                        }
-                        ControlFlow::Continue(())
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-            },
-            (true, false) => match (node, max, last) {
-                (None, None, None) => {
-                    let mut f = |chain: Chain| {
-                        let addr = chain.addr();
-                        let Some((Range { start, .. }, name)) = self.index.get_key_value(addr) else {
-                            return ControlFlow::Continue(());
-                        };
-                        match write!(writer, "{name}+{}", addr - start)
-                            .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                            .and(writeln!(writer))
-                        {
-                            Ok(_) => ControlFlow::Continue(()),
-                            Err(err) => ControlFlow::Break(Err(err)),
-                        }
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (None, None, Some(last)) => {
-                    let mut f = |chain: Chain| {
-                        if chain.last().is_some_and(|o| last.eq(o)) {
-                            let addr = chain.addr();
-                            let Some((Range { start, .. }, name)) = self.index.get_key_value(addr) else {
-                                return ControlFlow::Continue(());
-                            };
-                            return match write!(writer, "{name}+{}", addr - start)
-                                .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                .and(writeln!(writer))
-                            {
-                                Ok(_) => ControlFlow::Continue(()),
-                                Err(err) => ControlFlow::Break(Err(err)),
-                            };
-                        }
-                        ControlFlow::Continue(())
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (None, Some(max), None) => {
-                    let mut n = 0;
-                    let mut f = |chain: Chain| {
-                        if n >= max {
-                            return ControlFlow::Break(Ok(()));
-                        }
-                        let Some((Range { start, .. }, name)) = self.index.get_key_value(addr) else {
-                            return ControlFlow::Continue(());
-                        };
-                        match write!(writer, "{name}+{}", addr - start)
-                            .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                            .and(writeln!(writer))
-                        {
-                            Ok(_) => {
-                                n += 1;
-                                ControlFlow::Continue(())
-                            }
-                            Err(err) => ControlFlow::Break(Err(err)),
-                        }
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (None, Some(max), Some(last)) => {
-                    let mut n = 0;
-                    let mut f = |chain: Chain| {
-                        if n >= max {
-                            return ControlFlow::Break(Ok(()));
-                        }
-                        if chain.last().is_some_and(|o| last.eq(o)) {
-                            let addr = chain.addr();
-                            let Some((Range { start, .. }, name)) = self.index.get_key_value(addr) else {
-                                return ControlFlow::Continue(());
-                            };
-                            return match write!(writer, "{name}+{}", addr - start)
-                                .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                .and(writeln!(writer))
-                            {
-                                Ok(_) => {
-                                    n += 1;
-                                    ControlFlow::Continue(())
-                                }
-                                Err(err) => ControlFlow::Break(Err(err)),
-                            };
-                        }
-                        ControlFlow::Continue(())
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (Some(node), None, None) => {
-                    let mut f = |chain: Chain| {
-                        if chain.len() >= node {
-                            let addr = chain.addr();
-                            let Some((Range { start, .. }, name)) = self.index.get_key_value(addr) else {
-                                return ControlFlow::Continue(());
-                            };
-                            return match write!(writer, "{name}+{}", addr - start)
-                                .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                .and(writeln!(writer))
-                            {
-                                Ok(_) => ControlFlow::Continue(()),
-                                Err(err) => ControlFlow::Break(Err(err)),
-                            };
-                        }
-                        ControlFlow::Continue(())
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (Some(node), None, Some(last)) => {
-                    let mut f = |chain: Chain| {
-                        if chain.len() >= node && chain.last().is_some_and(|o| last.eq(o)) {
-                            let addr = chain.addr();
-                            let Some((Range { start, .. }, name)) = self.index.get_key_value(addr) else {
-                                return ControlFlow::Continue(());
-                            };
-                            return match write!(writer, "{name}+{}", addr - start)
-                                .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                .and(writeln!(writer))
-                            {
-                                Ok(_) => ControlFlow::Continue(()),
-                                Err(err) => ControlFlow::Break(Err(err)),
-                            };
-                        }
-                        ControlFlow::Continue(())
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (Some(node), Some(max), None) => {
-                    let mut n = 0;
-                    let mut f = |chain: Chain| {
-                        if n >= max {
-                            return ControlFlow::Break(Ok(()));
-                        }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-                        if chain.len() >= node {
-                            let addr = chain.addr();
-                            let Some((Range { start, .. }, name)) = self.index.get_key_value(addr) else {
-                                return ControlFlow::Continue(());
-                            };
-                            return match write!(writer, "{name}+{}", addr - start)
-                                .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                .and(writeln!(writer))
-                            {
-                                Ok(_) => {
-                                    n += 1;
-                                    ControlFlow::Continue(())
-                                }
-                                Err(err) => ControlFlow::Break(Err(err)),
-                            };
-                        }
-                        ControlFlow::Continue(())
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (Some(node), Some(max), Some(last)) => {
-                    let mut n = 0;
-                    let mut f = |chain: Chain| {
-                        if n >= max {
-                            return ControlFlow::Break(Ok(()));
-                        }
-                        if chain.len() >= node && chain.last().is_some_and(|o| last.eq(o)) {
-                            let addr = chain.addr();
-                            let Some((Range { start, .. }, name)) = self.index.get_key_value(addr) else {
-                                return ControlFlow::Continue(());
-                            };
-                            return match write!(writer, "{name}+{}", addr - start)
-                                .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                .and(writeln!(writer))
-                            {
-                                Ok(_) => {
-                                    n += 1;
-                                    ControlFlow::Continue(())
-                                }
-                                Err(err) => ControlFlow::Break(Err(err)),
-                            };
-                        }
-                        ControlFlow::Continue(())
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-            },
-            (false, true) => match (node, max, last) {
-                (None, None, None) => {
-                    let mut f = |chain: Chain| {
-                        let addr = chain.addr();
-                        match chain.ref_cycle() {
-                            Some(mut iter) => match write!(writer, "{addr}")
-                                .and(iter.try_for_each(|o| write!(writer, ".{o}")))
-                                .and(writeln!(writer))
-                            {
-                                Ok(_) => ControlFlow::Continue(()),
-                                Err(err) => ControlFlow::Break(Err(err)),
-                            },
-                            None => match write!(writer, "{addr}")
-                                .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                .and(writeln!(writer))
-                            {
-                                Ok(_) => ControlFlow::Continue(()),
-                                Err(err) => ControlFlow::Break(Err(err)),
-                            },
-                        }
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (None, None, Some(last)) => {
-                    let mut f = |chain: Chain| {
-                        if chain.last().is_some_and(|o| last.eq(o)) {
-                            let addr = chain.addr();
-                            return match chain.ref_cycle() {
-                                Some(mut iter) => match write!(writer, "{addr}")
-                                    .and(iter.try_for_each(|o| write!(writer, ".{o}")))
-                                    .and(writeln!(writer))
-                                {
-                                    Ok(_) => ControlFlow::Continue(()),
-                                    Err(err) => ControlFlow::Break(Err(err)),
-                                },
-                                None => match write!(writer, "{addr}")
-                                    .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                    .and(writeln!(writer))
-                                {
-                                    Ok(_) => ControlFlow::Continue(()),
-                                    Err(err) => ControlFlow::Break(Err(err)),
-                                },
-                            };
-                        }
-                        ControlFlow::Continue(())
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (None, Some(max), None) => {
-                    let mut n = 0;
-                    let mut f = |chain: Chain| {
-                        if n >= max {
-                            return ControlFlow::Break(Ok(()));
-                        }
-                        match chain.ref_cycle() {
-                            Some(mut iter) => match write!(writer, "{addr}")
-                                .and(iter.try_for_each(|o| write!(writer, ".{o}")))
-                                .and(writeln!(writer))
-                            {
-                                Ok(_) => {
-                                    n += 1;
-                                    ControlFlow::Continue(())
-                                }
-                                Err(err) => ControlFlow::Break(Err(err)),
-                            },
-                            None => match write!(writer, "{addr}")
-                                .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                .and(writeln!(writer))
-                            {
-                                Ok(_) => {
-                                    n += 1;
-                                    ControlFlow::Continue(())
-                                }
-                                Err(err) => ControlFlow::Break(Err(err)),
-                            },
-                        }
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (None, Some(max), Some(last)) => {
-                    let mut n = 0;
-                    let mut f = |chain: Chain| {
-                        if n >= max {
-                            return ControlFlow::Break(Ok(()));
-                        }
-                        if chain.last().is_some_and(|o| last.eq(o)) {
-                            let addr = chain.addr();
-                            return match chain.ref_cycle() {
-                                Some(mut iter) => {
-                                    match write!(writer, "{addr}")
-                                        .and(iter.try_for_each(|o| write!(writer, ".{o}")))
-                                        .and(writeln!(writer))
-                                    {
-                                        Ok(_) => {
-                                            n += 1;
-                                            ControlFlow::Continue(())
-                                        }
-                                        Err(err) => ControlFlow::Break(Err(err)),
-                                    }
-                                }
-                                None => match write!(writer, "{addr}")
-                                    .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                    .and(writeln!(writer))
-                                {
-                                    Ok(_) => {
-                                        n += 1;
-                                        ControlFlow::Continue(())
-                                    }
-                                    Err(err) => ControlFlow::Break(Err(err)),
-                                },
-                            };
-                        }
-                        ControlFlow::Continue(())
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (Some(node), None, None) => {
-                    let mut f = |chain: Chain| {
-                        if chain.len() >= node {
-                            let addr = chain.addr();
-                            return match chain.ref_cycle() {
-                                Some(mut iter) => match write!(writer, "{addr}")
-                                    .and(iter.try_for_each(|o| write!(writer, ".{o}")))
-                                    .and(writeln!(writer))
-                                {
-                                    Ok(_) => ControlFlow::Continue(()),
-                                    Err(err) => ControlFlow::Break(Err(err)),
-                                },
-                                None => match write!(writer, "{addr}")
-                                    .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                    .and(writeln!(writer))
-                                {
-                                    Ok(_) => ControlFlow::Continue(()),
-                                    Err(err) => ControlFlow::Break(Err(err)),
-                                },
-                            };
-                        }
-                        ControlFlow::Continue(())
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (Some(node), None, Some(last)) => {
-                    let mut f = |chain: Chain| {
-                        if chain.len() >= node && chain.last().is_some_and(|o| last.eq(o)) {
-                            let addr = chain.addr();
-                            return match chain.ref_cycle() {
-                                Some(mut iter) => match write!(writer, "{addr}")
-                                    .and(iter.try_for_each(|o| write!(writer, ".{o}")))
-                                    .and(writeln!(writer))
-                                {
-                                    Ok(_) => ControlFlow::Continue(()),
-                                    Err(err) => ControlFlow::Break(Err(err)),
-                                },
-                                None => match write!(writer, "{addr}")
-                                    .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                    .and(writeln!(writer))
-                                {
-                                    Ok(_) => ControlFlow::Continue(()),
-                                    Err(err) => ControlFlow::Break(Err(err)),
-                                },
-                            };
-                        }
-                        ControlFlow::Continue(())
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (Some(node), Some(max), None) => {
-                    let mut n = 0;
-                    let mut f = |chain: Chain| {
-                        if n >= max {
-                            return ControlFlow::Break(Ok(()));
-                        }
+    fn pointer_map(modules: &[(Range<usize>, &str)], pairs: &[(usize, usize)], base: ModuleBase) -> Vec<u8> {
+        let modules = modules.iter().map(|(r, n)| (r.clone(), n.to_string())).collect();
+        let header = Header::new(1, "app".into(), modules, Layout::NATIVE, Layout::NATIVE.width(), base);
+        let mut writer = PointerMapWriter::new(Cursor::new(Vec::new()), header).unwrap();
+        pairs.iter().for_each(|&(k, v)| writer.write_pair(k, v).unwrap());
+        writer.finish().unwrap().into_inner()
+    }
 
-                        if chain.len() >= node {
-                            let addr = chain.addr();
-                            return match chain.ref_cycle() {
-                                Some(mut iter) => match write!(writer, "{addr}")
-                                    .and(iter.try_for_each(|o| write!(writer, ".{o}")))
-                                    .and(writeln!(writer))
-                                {
-                                    Ok(_) => {
-                                        n += 1;
-                                        ControlFlow::Continue(())
-                                    }
-                                    Err(err) => ControlFlow::Break(Err(err)),
-                                },
-                                None => match write!(writer, "{addr}")
-                                    .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                    .and(writeln!(writer))
-                                {
-                                    Ok(_) => {
-                                        n += 1;
-                                        ControlFlow::Continue(())
-                                    }
-                                    Err(err) => ControlFlow::Break(Err(err)),
-                                },
-                            };
-                        }
-                        ControlFlow::Continue(())
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (Some(node), Some(max), Some(last)) => {
-                    let mut n = 0;
-                    let mut f = |chain: Chain| {
-                        if n >= max {
-                            return ControlFlow::Break(Ok(()));
-                        }
-                        if chain.len() >= node && chain.last().is_some_and(|o| last.eq(o)) {
-                            let addr = chain.addr();
-                            return match chain.ref_cycle() {
-                                Some(mut iter) => match write!(writer, "{addr}")
-                                    .and(iter.try_for_each(|o| write!(writer, ".{o}")))
-                                    .and(writeln!(writer))
-                                {
-                                    Ok(_) => {
-                                        n += 1;
-                                        ControlFlow::Continue(())
-                                    }
-                                    Err(err) => ControlFlow::Break(Err(err)),
-                                },
-                                None => match write!(writer, "{addr}")
-                                    .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                    .and(writeln!(writer))
-                                {
-                                    Ok(_) => {
-                                        n += 1;
-                                        ControlFlow::Continue(())
-                                    }
-                                    Err(err) => ControlFlow::Break(Err(err)),
-                                },
-                            };
-                        }
-                        ControlFlow::Continue(())
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-            },
-            (false, false) => match (node, max, last) {
-                (None, None, None) => {
-                    let mut f = |chain: Chain| {
-                        let addr = chain.addr();
-                        match write!(writer, "{addr}")
-                            .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                            .and(writeln!(writer))
-                        {
-                            Ok(_) => ControlFlow::Continue(()),
-                            Err(err) => ControlFlow::Break(Err(err)),
-                        }
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (None, None, Some(last)) => {
-                    let mut f = |chain: Chain| {
-                        if chain.last().is_some_and(|o| last.eq(o)) {
-                            let addr = chain.addr();
-                            return match write!(writer, "{addr}")
-                                .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                .and(writeln!(writer))
-                            {
-                                Ok(_) => ControlFlow::Continue(()),
-                                Err(err) => ControlFlow::Break(Err(err)),
-                            };
-                        }
-                        ControlFlow::Continue(())
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (None, Some(max), None) => {
-                    let mut n = 0;
-                    let mut f = |chain: Chain| {
-                        if n >= max {
-                            return ControlFlow::Break(Ok(()));
-                        }
-                        let addr = chain.addr();
-                        match write!(writer, "{addr}")
-                            .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                            .and(writeln!(writer))
-                        {
-                            Ok(_) => {
-                                n += 1;
-                                ControlFlow::Continue(())
-                            }
-                            Err(err) => ControlFlow::Break(Err(err)),
-                        }
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (None, Some(max), Some(last)) => {
-                    let mut n = 0;
-                    let mut f = |chain: Chain| {
-                        if n >= max {
-                            return ControlFlow::Break(Ok(()));
-                        }
-                        if chain.last().is_some_and(|o| last.eq(o)) {
-                            let addr = chain.addr();
-                            return match write!(writer, "{addr}")
-                                .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                .and(writeln!(writer))
-                            {
-                                Ok(_) => {
-                                    n += 1;
-                                    ControlFlow::Continue(())
-                                }
-                                Err(err) => ControlFlow::Break(Err(err)),
-                            };
-                        }
-                        ControlFlow::Continue(())
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (Some(node), None, None) => {
-                    let mut f = |chain: Chain| {
-                        if chain.len() >= node {
-                            let addr = chain.addr();
-                            return match write!(writer, "{addr}")
-                                .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                .and(writeln!(writer))
-                            {
-                                Ok(_) => ControlFlow::Continue(()),
-                                Err(err) => ControlFlow::Break(Err(err)),
-                            };
-                        }
-                        ControlFlow::Continue(())
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (Some(node), None, Some(last)) => {
-                    let mut f = |chain: Chain| {
-                        if chain.len() >= node && chain.last().is_some_and(|o| last.eq(o)) {
-                            let addr = chain.addr();
-                            return match write!(writer, "{addr}")
-                                .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                .and(writeln!(writer))
-                            {
-                                Ok(_) => ControlFlow::Continue(()),
-                                Err(err) => ControlFlow::Break(Err(err)),
-                            };
-                        }
-                        ControlFlow::Continue(())
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (Some(node), Some(max), None) => {
-                    let mut n = 0;
-                    let mut f = |chain: Chain| {
-                        if n >= max {
-                            return ControlFlow::Break(Ok(()));
-                        }
+    type State = (Vec<(Range<usize>, String)>, Vec<(usize, usize)>, Option<ModuleBase>);
 
-                        if chain.len() >= node {
-                            let addr = chain.addr();
-                            return match write!(writer, "{addr}")
-                                .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                .and(writeln!(writer))
-                            {
-                                Ok(_) => {
-                                    n += 1;
-                                    ControlFlow::Continue(())
-                                }
-                                Err(err) => ControlFlow::Break(Err(err)),
-                            };
-                        }
-                        ControlFlow::Continue(())
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-                (Some(node), Some(max), Some(last)) => {
-                    let mut n = 0;
-                    let mut f = |chain: Chain| {
-                        if n >= max {
-                            return ControlFlow::Break(Ok(()));
-                        }
-                        if chain.len() >= node && chain.last().is_some_and(|o| last.eq(o)) {
-                            let addr = chain.addr();
-                            return match write!(writer, "{addr}")
-                                .and(chain.data().try_for_each(|o| write!(writer, ".{o}")))
-                                .and(writeln!(writer))
-                            {
-                                Ok(_) => {
-                                    n += 1;
-                                    ControlFlow::Continue(())
-                                }
-                                Err(err) => ControlFlow::Break(Err(err)),
-                            };
-                        }
-                        ControlFlow::Continue(())
-                    };
-                    match try_pointer_chain_scan(&self.map, points, param, &mut f) {
-                        ControlFlow::Continue(_) => Ok(()),
-                        ControlFlow::Break(b) => b,
-                    }
-                }
-            },
-        }?;
+    fn state(scanner: &PtrsxScanner) -> State {
+        let modules = scanner.index.iter().map(|(r, n)| (r.clone(), n.clone())).collect();
+        let graph = &scanner.graph;
+        let pairs = graph
+            .points()
+            .iter()
+            .map(|&addr| (addr, graph.read(addr).unwrap()))
+            .collect();
+        (modules, pairs, scanner.module_base)
+    }
 
-        Ok(())
+    #[test]
+    fn corrupt_map_leaves_scanner_unchanged() {
+        let modules = [(0x1000..0x2000, "app[0]")];
+        let mut corrupt = pointer_map(&modules, &[(0x1008, 0x1000), (0x1010, 0x1008)], ModuleBase::Writable);
+        *corrupt.last_mut().unwrap() ^= 1;
+
+        let mut scanner = PtrsxScanner::default();
+        let result = scanner.load_pointer_map(corrupt.as_slice());
+        assert!(matches!(result, Err(Error::Checksum)));
+        assert_eq!(state(&scanner), (vec![], vec![], None));
+
+        let valid = pointer_map(&modules, &[(0x1000, 0x1008)], ModuleBase::Writable);
+        scanner.load_pointer_map(valid.as_slice()).unwrap();
+        let loaded = state(&scanner);
+        let result = scanner.load_pointer_map(corrupt.as_slice());
+        assert!(matches!(result, Err(Error::Checksum)));
+        assert_eq!(state(&scanner), loaded);
+        assert_eq!(loaded.1, [(0x1000, 0x1008)]);
     }
 
-    pub fn reset(&mut self) {
-        self.index.clear();
-        self.points.clear();
-        self.map.clear();
+    #[test]
+    fn modules_info_mismatch_in_any_order() {
+        let map = pointer_map(&[(0x1000..0x2000, "app[0]")], &[], ModuleBase::Writable);
+        let info = pointer_map(&[(0x1000..0x3000, "app[0]")], &[], ModuleBase::Writable);
+
+        let mut scanner = PtrsxScanner::default();
+        scanner.load_pointer_map(map.as_slice()).unwrap();
+        assert!(matches!(scanner.load_modules_info(info.as_slice()), Err(Error::Mismatch)));
+        assert_eq!(state(&scanner).0, [(0x1000..0x2000, "app[0]".to_string())]);
+
+        let mut scanner = PtrsxScanner::default();
+        scanner.load_modules_info(info.as_slice()).unwrap();
+        assert!(matches!(scanner.load_pointer_map(map.as_slice()), Err(Error::Mismatch)));
+        scanner.load_modules_info(info.as_slice()).unwrap();
+        assert_eq!(state(&scanner).0, [(0x1000..0x3000, "app[0]".to_string())]);
     }
 }
@@ -1,3 +1,27 @@
+use super::ProcessInfo;
+
+// mapping_filter 需要的内存区域信息，Windows 上还需要区域的状态
+#[cfg(not(target_os = "windows"))]
+pub trait FilterQuery: vmmap::VirtualQuery {}
+
+#[cfg(not(target_os = "windows"))]
+impl<T: vmmap::VirtualQuery> FilterQuery for T {}
+
+#[cfg(target_os = "windows")]
+pub trait FilterQuery: vmmap::VirtualQuery + vmmap::windows::VirtualQueryExt {}
+
+#[cfg(target_os = "windows")]
+impl<T: vmmap::VirtualQuery + vmmap::windows::VirtualQueryExt> FilterQuery for T {}
+
+// 数据来源能判断时使用来源的结果，否则读取本机的文件判断
+#[inline]
+pub fn source_filter<P: ProcessInfo, Q: FilterQuery>(proc: &P, page: &Q) -> bool {
+    match page.name().and_then(|name| proc.is_module(name)) {
+        Some(module) => module,
+        None => mapping_filter(page),
+    }
+}
+
 #[cfg(target_os = "macos")]
 #[inline]
 pub fn mapping_filter<Q: vmmap::VirtualQuery>(page: &Q) -> bool {
@@ -0,0 +1,81 @@
+// 判断模块能否作为指针链的基址
+// include 为空时允许全部模块，exclude 优先于 include
+// 模式可以是完整的模块名，也可以使用通配符 * 和 ?
+// 模块名末尾的 [n] 可以省略，例如 libc.so.6 匹配 libc.so.6[0] 和 libc.so.6[1]
+pub fn is_base_module(name: &str, include: &[String], exclude: &[String]) -> bool {
+    let matches = |pat: &String| {
+        glob_match(pat.as_bytes(), name.as_bytes()) || glob_match(pat.as_bytes(), strip_index(name).as_bytes())
+    };
+    (include.is_empty() || include.iter().any(matches)) && !exclude.iter().any(matches)
+}
+
+// 去掉模块名末尾的 [n]
+fn strip_index(name: &str) -> &str {
+    name.strip_suffix(']')
+        .and_then(|s| s.rsplit_once('['))
+        .filter(|(_, n)| n.bytes().all(|b| b.is_ascii_digit()))
+        .map_or(name, |(s, _)| s)
+}
+
+// 只支持 * 和 ?，匹配失败时回溯到上一个 *
+fn glob_match(pat: &[u8], s: &[u8]) -> bool {
+    let (mut p, mut i) = (0, 0);
+    let mut star = None;
+    while i < s.len() {
+        match pat.get(p) {
+            Some(b'*') => {
+                star = Some((p, i));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == s[i] => {
+                p += 1;
+                i += 1;
+            }
+            _ => match star {
+                Some((sp, si)) => {
+                    p = sp + 1;
+                    i = si + 1;
+                    star = Some((sp, si + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pat[p..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glob(pat: &str, s: &str) -> bool {
+        glob_match(pat.as_bytes(), s.as_bytes())
+    }
+
+    #[test]
+    fn glob_patterns() {
+        assert!(glob("libc.so.6", "libc.so.6"));
+        assert!(!glob("libc.so", "libc.so.6"));
+        assert!(glob("lib*.so*", "libstdc++.so.6"));
+        assert!(glob("*", ""));
+        assert!(glob("**a", "a"));
+        assert!(glob("lib?.so", "libc.so"));
+        assert!(!glob("lib?.so", "lib.so"));
+        assert!(glob("*a*b", "xaxxab"));
+        assert!(!glob("*a*b", "xaxxa"));
+        assert!(!glob("", "a"));
+    }
+
+    #[test]
+    fn base_module_filter() {
+        let include = ["libc*".to_string(), "app".to_string()];
+        let exclude = ["libcrypto*".to_string()];
+        assert!(is_base_module("libc.so.6[1]", &include, &exclude));
+        assert!(is_base_module("app[0]", &include, &exclude));
+        assert!(!is_base_module("libcrypto.so.3[0]", &include, &exclude));
+        assert!(!is_base_module("libm.so.6[0]", &include, &exclude));
+        assert!(is_base_module("libm.so.6[0]", &[], &exclude));
+        assert_eq!(strip_index("a[b][12]"), "a[b]");
+        assert_eq!(strip_index("a[x]"), "a[x]");
+    }
+}
@@ -0,0 +1,115 @@
+use core::ops::Range;
+use std::collections::HashMap;
+
+use vmmap::VirtualQuery;
+
+use super::{
+    elf::load_segments,
+    mapping_filter::{source_filter, FilterQuery},
+    Module, ProcessInfo,
+};
+
+// 模块基址的计算方式，module+offset 中的 offset 相对于这个地址
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ModuleBase {
+    // 模块第一个可读写区域的起始地址
+    #[default]
+    Writable,
+    // 模块的加载地址，即该文件的第一个映射区域，与 readelf、IDA 等工具中的地址一致
+    Load,
+}
+
+impl core::fmt::Display for ModuleBase {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ModuleBase::Writable => write!(f, "writable"),
+            ModuleBase::Load => write!(f, "load"),
+        }
+    }
+}
+
+// 处理所有可用于基址的模块，maps 是进程的全部内存区域
+// 合并处于同一模块的读写区域，截断模块路径只保留模块名，
+// 检查如果有多个模块名相同但是路径不同的区域就在模块名后面加一个数字
+// 紧跟在模块读写区域后面的匿名区域如果属于该模块的 .bss，也合并到该模块
+// base 为 Load 时区域从模块的加载地址开始，包含只读和可执行的区域
+// 创建指针文件、解析指针链时都使用这个函数，保证模块名和基址一致
+// proc 是 maps 的来源，决定哪些区域可以作为模块
+pub fn base_modules<P, V>(proc: &P, maps: &[V], base: ModuleBase) -> Vec<(Range<usize>, String)>
+where
+    P: ProcessInfo,
+    V: FilterQuery,
+{
+    // 模块在 maps 中第一个读写区域的序号
+    let mut modules = Vec::<(usize, Module)>::new();
+    let mut bss_end = None;
+    let iter = maps.iter().enumerate();
+    for (i, x) in iter.filter(|(_, x)| x.is_read() && x.is_write() && source_filter(proc, *x)) {
+        match (x.name(), modules.last_mut()) {
+            (Some(name), Some((_, last))) if last.name == name => last.end = x.end(),
+            (Some(name), _) => {
+                modules.push((i, Module { start: x.start(), end: x.end(), name }));
+                bss_end = None;
+            }
+            (None, Some((idx, last))) if last.end == x.start() => {
+                let end = *bss_end.get_or_insert_with(|| elf_bss_end(proc, maps, *idx));
+                if let Some(end) = end.filter(|&end| x.start() < end) {
+                    last.end = x.end().min(end);
+                }
+            }
+            (None, _) => {}
+        }
+    }
+
+    let mut counts = HashMap::new();
+    modules
+        .into_iter()
+        .map(|(i, Module { start, end, name })| {
+            let start = match base {
+                ModuleBase::Writable => start,
+                ModuleBase::Load => load_base(maps, i),
+            };
+            // Windows 路径在其他平台上也按 \ 分割
+            let name = name.rsplit(['/', '\\']).next().unwrap_or(name);
+            let count = counts.entry(name).or_insert(0);
+            let name = format!("{name}[{count}]");
+            *count += 1;
+            (start..end, name)
+        })
+        .collect()
+}
+
+// 模块的加载地址，从 maps[idx] 向前查找同一个文件的映射区域，
+// 跳过匿名区域，遇到其他文件时停止
+fn load_base<V: VirtualQuery>(maps: &[V], idx: usize) -> usize {
+    let name = maps[idx].name();
+    maps[..=idx]
+        .iter()
+        .rev()
+        .filter(|x| x.name().is_some())
+        .take_while(|x| x.name() == name)
+        .last()
+        .map_or(maps[idx].start(), |x| x.start())
+}
+
+const PAGE_SIZE: usize = 0x1000;
+
+// 根据 ELF 程序头计算模块 .bss 结束的地址，按页对齐
+// 模块文件不在本机时无法读取程序头，返回 None
+fn elf_bss_end<P: ProcessInfo, V: VirtualQuery>(proc: &P, maps: &[V], idx: usize) -> Option<usize> {
+    let name = maps[idx].name()?;
+    if proc.is_module(name).is_some() {
+        return None;
+    }
+    let segments = load_segments(name)?;
+    let first = segments.iter().map(|s| s.vaddr).min()? & !(PAGE_SIZE - 1);
+    let end = segments
+        .iter()
+        .filter(|s| s.writable)
+        .map(|s| s.vaddr.checked_add(s.memsz))
+        .max()??;
+    load_base(maps, idx)
+        .checked_sub(first)?
+        .checked_add(end)?
+        .checked_next_multiple_of(PAGE_SIZE)
+}
@@ -0,0 +1,45 @@
+use std::collections::HashMap;
+
+use super::{pointer_graph::PointerGraph, PointerChain, ResolveError};
+
+// 使用指针文件代替进程内存解析指针链，用于离线验证其他时间生成的指针链
+// 只能读取指针文件中记录的指针，每一步读取的地址都必须在指针文件中
+pub struct OfflineResolver<'a> {
+    modules: HashMap<&'a str, usize>,
+    graph: &'a PointerGraph,
+}
+
+impl<'a> OfflineResolver<'a> {
+    pub(crate) fn new(modules: HashMap<&'a str, usize>, graph: &'a PointerGraph) -> Self {
+        Self { modules, graph }
+    }
+
+    // 模块基址
+    pub fn module(&self, name: &str) -> Option<usize> {
+        self.modules.get(name).copied()
+    }
+
+    // 指针文件中 addr 储存的指针
+    pub fn read(&self, addr: usize) -> Option<usize> {
+        self.graph.read(addr)
+    }
+
+    pub fn resolve(&self, chain: &PointerChain) -> Result<usize, ResolveError> {
+        let base = match &chain.module {
+            Some(name) => self
+                .module(name)
+                .ok_or_else(|| ResolveError::UnknownModule(name.clone()))?,
+            None => 0,
+        };
+        let mut addr = base
+            .checked_add(chain.offset)
+            .ok_or(ResolveError::Overflow { step: None })?;
+        for (step, &offset) in chain.offsets.iter().enumerate() {
+            let value = self.read(addr).ok_or(ResolveError::Unreadable { step, addr })?;
+            addr = value
+                .checked_add_signed(offset)
+                .ok_or(ResolveError::Overflow { step: Some(step) })?;
+        }
+        Ok(addr)
+    }
+}
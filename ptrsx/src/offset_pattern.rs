@@ -0,0 +1,231 @@
+use core::{fmt, str::FromStr};
+
+use super::{error::Error, pointer_chain::parse_isize};
+
+// 单层偏移的约束
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OffsetRule {
+    // 任意偏移，写作 ?
+    Any,
+    // [min, max] 之间的偏移，写作 0x18 或 [0x10..0x40]，两端都包含
+    Range(isize, isize),
+}
+
+impl OffsetRule {
+    #[inline]
+    pub const fn bounds(&self) -> (isize, isize) {
+        match *self {
+            OffsetRule::Any => (isize::MIN, isize::MAX),
+            OffsetRule::Range(min, max) => (min, max),
+        }
+    }
+}
+
+// 指针链偏移模式，例如 *.0x18.?.[0x10..0x40]
+// 按从基址到目标地址的顺序，每层一个约束，不包含基址的模块偏移
+// 开头的 * 表示前面可以有任意数量的偏移，否则指针链长度必须与模式相同
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OffsetPattern {
+    rules: Vec<OffsetRule>,
+    prefix: bool,
+}
+
+impl Default for OffsetPattern {
+    fn default() -> Self {
+        Self::ANY
+    }
+}
+
+impl OffsetPattern {
+    // 不限制任何偏移，等同于 *
+    pub const ANY: Self = Self { rules: Vec::new(), prefix: true };
+
+    pub const fn new(rules: Vec<OffsetRule>, prefix: bool) -> Self {
+        Self { rules, prefix }
+    }
+
+    pub fn rules(&self) -> &[OffsetRule] {
+        &self.rules
+    }
+
+    // 距离目标地址第 n 层偏移的约束，n 为 0 时是最后一个偏移
+    // None 表示指针链不能达到这个长度
+    #[inline]
+    pub fn rule(&self, n: usize) -> Option<OffsetRule> {
+        match self.rules.len().checked_sub(n + 1) {
+            Some(i) => Some(self.rules[i]),
+            None => self.prefix.then_some(OffsetRule::Any),
+        }
+    }
+
+    // 长度为 len 的指针链是否可能匹配
+    #[inline]
+    pub fn accepts(&self, len: usize) -> bool {
+        match self.prefix {
+            true => len >= self.rules.len(),
+            false => len == self.rules.len(),
+        }
+    }
+}
+
+impl FromStr for OffsetPattern {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || Error::Pattern(s.to_string());
+        let mut parts = split(s).map(str::trim).peekable();
+        let prefix = parts.next_if_eq(&"*").is_some();
+        let mut rules = Vec::new();
+        for part in parts {
+            let rule = match part {
+                "?" => OffsetRule::Any,
+                _ => match part.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+                    Some(r) => {
+                        let (min, max) = r.split_once("..").ok_or_else(err)?;
+                        let min = match min.trim() {
+                            "" => isize::MIN,
+                            min => parse_isize(min).ok_or_else(err)?,
+                        };
+                        let max = match max.trim() {
+                            "" => isize::MAX,
+                            max => parse_isize(max).ok_or_else(err)?,
+                        };
+                        if min > max {
+                            return Err(err());
+                        }
+                        OffsetRule::Range(min, max)
+                    }
+                    None => {
+                        let o = parse_isize(part).ok_or_else(err)?;
+                        OffsetRule::Range(o, o)
+                    }
+                },
+            };
+            rules.push(rule);
+        }
+        Ok(Self { rules, prefix })
+    }
+}
+
+// 按 . 拆分，跳过 [] 中的 ..
+fn split(s: &str) -> impl Iterator<Item = &str> {
+    let mut depth = 0i32;
+    s.split(move |c| {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            _ => {}
+        }
+        c == '.' && depth == 0
+    })
+}
+
+fn write_offset(f: &mut fmt::Formatter<'_>, o: isize) -> fmt::Result {
+    match o < 0 {
+        true => write!(f, "-{:#x}", o.unsigned_abs()),
+        false => write!(f, "{o:#x}"),
+    }
+}
+
+impl fmt::Display for OffsetRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            OffsetRule::Any => write!(f, "?"),
+            OffsetRule::Range(min, max) if min == max => write_offset(f, min),
+            OffsetRule::Range(min, max) => {
+                write!(f, "[")?;
+                if min != isize::MIN {
+                    write_offset(f, min)?;
+                }
+                write!(f, "..")?;
+                if max != isize::MAX {
+                    write_offset(f, max)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+impl fmt::Display for OffsetPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut first = true;
+        if self.prefix {
+            write!(f, "*")?;
+            first = false;
+        }
+        for rule in &self.rules {
+            if !first {
+                write!(f, ".")?;
+            }
+            write!(f, "{rule}")?;
+            first = false;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rules() {
+        let pattern = "*.0x18.?.[0x10..0x40].-8".parse::<OffsetPattern>().unwrap();
+        let rules = [
+            OffsetRule::Range(0x18, 0x18),
+            OffsetRule::Any,
+            OffsetRule::Range(0x10, 0x40),
+            OffsetRule::Range(-8, -8),
+        ];
+        assert_eq!(pattern, OffsetPattern::new(rules.to_vec(), true));
+        assert_eq!(pattern.to_string(), "*.0x18.?.[0x10..0x40].-0x8");
+        assert_eq!(pattern.to_string().parse::<OffsetPattern>().unwrap(), pattern);
+    }
+
+    #[test]
+    fn parse_open_ranges() {
+        let pattern = "[..-0x10].[ 0x20 .. ].+16".parse::<OffsetPattern>().unwrap();
+        let rules = [
+            OffsetRule::Range(isize::MIN, -0x10),
+            OffsetRule::Range(0x20, isize::MAX),
+            OffsetRule::Range(16, 16),
+        ];
+        assert_eq!(pattern, OffsetPattern::new(rules.to_vec(), false));
+        assert_eq!(pattern.to_string(), "[..-0x10].[0x20..].0x10");
+        assert_eq!("*".parse::<OffsetPattern>().unwrap(), OffsetPattern::ANY);
+    }
+
+    #[test]
+    fn rule_and_accepts() {
+        let exact = "0x10.?".parse::<OffsetPattern>().unwrap();
+        assert_eq!(exact.rule(0), Some(OffsetRule::Any));
+        assert_eq!(exact.rule(1), Some(OffsetRule::Range(0x10, 0x10)));
+        assert_eq!(exact.rule(2), None);
+        assert!(exact.accepts(2) && !exact.accepts(1) && !exact.accepts(3));
+
+        let prefix = "*.0x10".parse::<OffsetPattern>().unwrap();
+        assert_eq!(prefix.rule(3), Some(OffsetRule::Any));
+        assert!(prefix.accepts(1) && prefix.accepts(5) && !prefix.accepts(0));
+    }
+
+    #[test]
+    fn parse_errors() {
+        for s in [
+            "",
+            "--5",
+            "-+5",
+            "+-5",
+            "0x-5",
+            "[-0x+10..]",
+            "0x18..8",
+            "[0x40..0x10]",
+            "[0x10]",
+            "0x10.*",
+            "0xzz",
+            "[0x10..0x20",
+        ] {
+            assert!(matches!(s.parse::<OffsetPattern>(), Err(Error::Pattern(p)) if p == s), "{s}");
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use core::{fmt, str::FromStr};
+
+// 指针链扫描结果，文本格式为 module+offset.o1.o2 或者 offset.o1.o2
+// module 为 None 时 offset 是基址的绝对地址
+// 解析时偏移可以是十进制或者 0x 开头的十六进制，{:#} 以十六进制输出
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct PointerChain {
+    pub module: Option<String>,
+    pub offset: usize,
+    pub offsets: Vec<isize>,
+}
+
+impl fmt::Display for PointerChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(name) = &self.module {
+            write!(f, "{name}+")?;
+        }
+        match f.alternate() {
+            true => {
+                write!(f, "{:#x}", self.offset)?;
+                self.offsets.iter().try_for_each(|&o| match o < 0 {
+                    true => write!(f, ".-{:#x}", o.unsigned_abs()),
+                    false => write!(f, ".{o:#x}"),
+                })
+            }
+            false => {
+                write!(f, "{}", self.offset)?;
+                self.offsets.iter().try_for_each(|o| write!(f, ".{o}"))
+            }
+        }
+    }
+}
+
+// 指针链解析错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseChainError {
+    // 空字符串
+    Empty,
+    // + 前面的模块名为空
+    EmptyModule,
+    // 基址偏移无法解析
+    InvalidBase(String),
+    // 第 n 个偏移无法解析，从 0 开始
+    InvalidOffset(usize, String),
+}
+
+impl fmt::Display for ParseChainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseChainError::Empty => write!(f, "empty pointer chain"),
+            ParseChainError::EmptyModule => write!(f, "empty module name"),
+            ParseChainError::InvalidBase(s) => write!(f, "invalid base offset: `{s}`"),
+            ParseChainError::InvalidOffset(n, s) => write!(f, "invalid offset #{n}: `{s}`"),
+        }
+    }
+}
+
+impl std::error::Error for ParseChainError {}
+
+// 十进制或者 0x 开头的十六进制，不带符号
+pub fn parse_usize(s: &str) -> Option<usize> {
+    let (s, radix) = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(s) => (s, 16),
+        None => (s, 10),
+    };
+    match s.starts_with('+') {
+        true => None,
+        false => usize::from_str_radix(s, radix).ok(),
+    }
+}
+
+// 最多带一个 + 或者 - 符号
+pub fn parse_isize(s: &str) -> Option<isize> {
+    match s.strip_prefix('-') {
+        Some(s) => 0_isize.checked_sub_unsigned(parse_usize(s)?),
+        None => isize::try_from(parse_usize(s.strip_prefix('+').unwrap_or(s))?).ok(),
+    }
+}
+
+// 解析 + 之后的 offset.o1.o2
+fn parse_offsets(s: &str) -> Result<(usize, Vec<isize>), ParseChainError> {
+    let mut iter = s.split('.');
+    let base = iter.next().unwrap_or_default();
+    let offset = parse_usize(base).ok_or_else(|| ParseChainError::InvalidBase(base.to_string()))?;
+    let offsets = iter
+        .enumerate()
+        .map(|(n, o)| parse_isize(o).ok_or_else(|| ParseChainError::InvalidOffset(n, o.to_string())))
+        .collect::<Result<_, _>>()?;
+    Ok((offset, offsets))
+}
+
+impl FromStr for PointerChain {
+    type Err = ParseChainError;
+
+    // 模块名中可能包含 +，例如 libstdc++.so.6[0]，偏移也可以写作 +8
+    // 所以选择第一个使后面部分能完整解析的 +
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(ParseChainError::Empty);
+        }
+        if let Ok((offset, offsets)) = parse_offsets(s) {
+            return Ok(Self { module: None, offset, offsets });
+        }
+        let splits = || s.match_indices('+').map(|(i, _)| (&s[..i], &s[i + 1..]));
+        let found = splits().find_map(|(name, rest)| Some((name, parse_offsets(rest).ok()?)));
+        match found {
+            Some(("", _)) => Err(ParseChainError::EmptyModule),
+            Some((name, (offset, offsets))) => Ok(Self { module: Some(name.to_string()), offset, offsets }),
+            // 都不能解析时，错误来自第一个基址有效的 +，没有则来自最后一个 +
+            None => {
+                let rest = splits()
+                    .find(|(_, rest)| parse_usize(rest.split('.').next().unwrap_or_default()).is_some())
+                    .or_else(|| splits().next_back())
+                    .map_or(s, |(_, rest)| rest);
+                Err(parse_offsets(rest).err().unwrap_or(ParseChainError::Empty))
+            }
+        }
+    }
+}
+
+// 关联扫描结果，prefix 是共享的指针链，解引用后得到同一个对象
+// fields 是每个目标地址相对该对象的偏移，顺序与目标地址相同
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct FieldChain {
+    pub prefix: PointerChain,
+    pub fields: Vec<isize>,
+}
+
+impl fmt::Display for FieldChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.[", self.prefix)?;
+        for (i, o) in self.fields.iter().enumerate() {
+            match i {
+                0 => write!(f, "{o}")?,
+                _ => write!(f, ",{o}")?,
+            }
+        }
+        write!(f, "]")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain(module: Option<&str>, offset: usize, offsets: &[isize]) -> PointerChain {
+        PointerChain { module: module.map(Into::into), offset, offsets: offsets.to_vec() }
+    }
+
+    #[test]
+    fn parse_and_display() {
+        let c = chain(Some("libstdc++.so.6[0]"), 0x10, &[8, -0x10, 0]);
+        assert_eq!(c.to_string(), "libstdc++.so.6[0]+16.8.-16.0");
+        assert_eq!(format!("{c:#}"), "libstdc++.so.6[0]+0x10.0x8.-0x10.0x0");
+        assert_eq!(c.to_string().parse(), Ok(c.clone()));
+        assert_eq!(format!("{c:#}").parse(), Ok(c));
+
+        let c = chain(None, 0x7f00, &[0x18]);
+        assert_eq!(c.to_string().parse(), Ok(c.clone()));
+        assert_eq!(" 0x7f00.0x18 \n".parse(), Ok(c));
+    }
+
+    #[test]
+    fn parse_signed_offsets() {
+        let c = chain(Some("app[1]"), 16, &[8, -8]);
+        assert_eq!("app[1]+16.+8.-8".parse(), Ok(c.clone()));
+        assert_eq!("app[1]+0x10.+0x8.-0x8".parse(), Ok(c));
+        let c = chain(Some("a+b[0]"), 0, &[8]);
+        assert_eq!("a+b[0]+0.+8".parse(), Ok(c));
+        assert_eq!("0x1000.+8".parse(), Ok(chain(None, 0x1000, &[8])));
+    }
+
+    #[test]
+    fn reject_doubled_signs() {
+        for s in ["--8", "-+8", "+-8", "++8", "0x+8", "0x-8", "-0x-8", "+0x+8"] {
+            assert_eq!(parse_isize(s), None, "{s}");
+        }
+        assert_eq!(parse_usize("+8"), None);
+        assert_eq!(parse_usize("0x+8"), None);
+        assert_eq!(parse_isize("+0x8"), Some(8));
+        assert_eq!(parse_isize("-0x8"), Some(-8));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!("  ".parse::<PointerChain>(), Err(ParseChainError::Empty));
+        assert_eq!("+8".parse::<PointerChain>(), Err(ParseChainError::EmptyModule));
+        assert_eq!("app[0]+x.8".parse::<PointerChain>(), Err(ParseChainError::InvalidBase("x".into())));
+        assert_eq!("app[0]+0.8.y".parse::<PointerChain>(), Err(ParseChainError::InvalidOffset(1, "y".into())));
+        assert_eq!("app[0]+0.8+".parse::<PointerChain>(), Err(ParseChainError::InvalidOffset(0, "8+".into())));
+        assert_eq!("app[0]+0.--8".parse::<PointerChain>(), Err(ParseChainError::InvalidOffset(0, "--8".into())));
+        assert_eq!("lib++.so+0.y".parse::<PointerChain>(), Err(ParseChainError::InvalidOffset(0, "y".into())));
+    }
+}
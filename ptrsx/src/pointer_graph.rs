@@ -0,0 +1,323 @@
+use core::{cmp::Ordering, mem, slice};
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::Path,
+    process,
+};
+
+use memmap2::Mmap;
+
+use super::error::{Error, Result};
+
+// 反向指针索引文件格式，头部字段为小端序，数组使用主机的字节序和指针宽度，
+// 可以直接映射到内存
+//
+// magic[8] version:u32 width:u8 endian:u8 reserved[2]
+// source:u64 len:u64 count:u64
+// points[count] targets[count] addrs[count] offsets[len + 1] values[len]
+//
+// source 是对应指针文件的校验和，用于判断索引是否过期
+pub const INDEX_MAGIC: [u8; 8] = *b"PTRSXIDX";
+const INDEX_VERSION: u32 = 3;
+const INDEX_HEADER_LEN: usize = 40;
+
+#[cfg(target_endian = "little")]
+const NATIVE_ENDIAN: u8 = 0;
+#[cfg(target_endian = "big")]
+const NATIVE_ENDIAN: u8 = 1;
+
+enum Words {
+    Owned(Vec<usize>),
+    Mapped(Mmap),
+}
+
+impl Words {
+    #[inline]
+    fn as_slice(&self) -> &[usize] {
+        match self {
+            Words::Owned(words) => words,
+            // open 时已经检查过长度和对齐
+            Words::Mapped(mmap) => unsafe {
+                let data = &mmap[INDEX_HEADER_LEN..];
+                slice::from_raw_parts(data.as_ptr().cast(), data.len() / mem::size_of::<usize>())
+            },
+        }
+    }
+}
+
+// CSR 结构的反向指针索引，全部数组均已排序
+// values 是所有不重复的指针值
+// addrs[offsets[i]..offsets[i + 1]] 是储存了 values[i] 的地址
+// points 是所有储存了指针的地址，targets[i] 是 points[i] 储存的指针值
+pub struct PointerGraph {
+    words: Words,
+    len: usize,
+    count: usize,
+}
+
+impl Default for PointerGraph {
+    fn default() -> Self {
+        Self { words: Words::Owned(vec![0]), len: 0, count: 0 }
+    }
+}
+
+impl PointerGraph {
+    // 合并已有的指针和新读取的 (地址,
+    // 指针值)，同一个地址出现多次时只保留第一个，已有的指针优先
+    pub fn merge(&self, points: Vec<usize>, targets: Vec<usize>) -> Self {
+        let (points, targets) = sort_pairs(points, targets);
+        if self.is_empty() {
+            return Self::from_sorted(points, targets);
+        }
+        let (old_points, old_targets) = (self.points(), self.targets());
+        let capacity = old_points.len() + points.len();
+        let (mut merged_points, mut merged_targets) = (Vec::with_capacity(capacity), Vec::with_capacity(capacity));
+        let (mut i, mut j) = (0, 0);
+        while i < old_points.len() || j < points.len() {
+            let take_old = j == points.len() || (i < old_points.len() && old_points[i] <= points[j]);
+            match take_old {
+                true => {
+                    if j < points.len() && points[j] == old_points[i] {
+                        j += 1;
+                    }
+                    merged_points.push(old_points[i]);
+                    merged_targets.push(old_targets[i]);
+                    i += 1;
+                }
+                false => {
+                    merged_points.push(points[j]);
+                    merged_targets.push(targets[j]);
+                    j += 1;
+                }
+            }
+        }
+        drop((points, targets));
+        Self::from_sorted(merged_points, merged_targets)
+    }
+
+    // points 按地址排序且不重复，targets[i] 是 points[i] 储存的指针值
+    // 先统计每个指针值的数量，再按地址顺序填入 addrs，区间内的地址自然有序，
+    // 不需要另外保存一份按指针值排序的 (地址, 指针值)
+    // 峰值约为 count * 24 + len * 24 字节，最终为 count * 24 + len * 16 字节
+    fn from_sorted(points: Vec<usize>, targets: Vec<usize>) -> Self {
+        let count = points.len();
+        let mut values = targets.clone();
+        values.sort_unstable();
+        values.dedup();
+        values.shrink_to_fit();
+        let len = values.len();
+
+        // points 的空间直接作为索引，大块内存扩容时通常不需要复制
+        let mut words = points;
+        words.reserve_exact(count);
+        words.extend(targets);
+        words.reserve_exact(count + len * 2 + 1);
+        words.resize(count * 3 + len + 1, 0);
+        words.extend(values);
+
+        let (head, tail) = words.split_at_mut(count * 3);
+        let (points, rest) = head.split_at_mut(count);
+        let (targets, addrs) = rest.split_at_mut(count);
+        let (offsets, values) = tail.split_at_mut(len + 1);
+        let position = |value: &usize| values.binary_search(value).unwrap_or_default();
+        // offsets[i + 1] 先记录 values[i] 的数量，再累加为区间的结束位置
+        targets.iter().for_each(|value| offsets[position(value) + 1] += 1);
+        (0..len).for_each(|i| offsets[i + 1] += offsets[i]);
+        // 以 offsets[i] 作为 values[i] 的填充位置，填充完成后右移一位恢复为起始位置
+        for (&addr, value) in points.iter().zip(targets.iter()) {
+            let i = position(value);
+            addrs[offsets[i]] = addr;
+            offsets[i] += 1;
+        }
+        offsets.copy_within(..len, 1);
+        offsets[0] = 0;
+        Self { words: Words::Owned(words), len, count }
+    }
+
+    // 返回映射后的索引和 source
+    // 映射期间索引文件不能被修改
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<(Self, u64)> {
+        let file = File::open(path)?;
+        let mmap = unsafe { Mmap::map(&file)? };
+        if mmap.len() < INDEX_HEADER_LEN {
+            return Err(Error::Truncated);
+        }
+        if mmap[..8] != INDEX_MAGIC {
+            return Err(Error::Corrupted);
+        }
+        let version = u32::from_le_bytes(mmap[8..12].try_into().unwrap());
+        if version != INDEX_VERSION {
+            return Err(Error::Version(version));
+        }
+        let (width, endian) = (mmap[12] as usize, mmap[13]);
+        if width != mem::size_of::<usize>() {
+            return Err(Error::PointerWidth(width));
+        }
+        if endian != NATIVE_ENDIAN {
+            return Err(Error::Corrupted);
+        }
+        let read_u64 = |pos: usize| u64::from_le_bytes(mmap[pos..pos + 8].try_into().unwrap());
+        let source = read_u64(16);
+        let len = read_u64(24) as usize;
+        let count = read_u64(32) as usize;
+
+        let total = len
+            .checked_mul(2)
+            .and_then(|n| n.checked_add(count.checked_mul(3)?))
+            .and_then(|n| n.checked_add(1)?.checked_mul(width))
+            .ok_or(Error::Corrupted)?;
+        match (mmap.len() - INDEX_HEADER_LEN).cmp(&total) {
+            Ordering::Less => return Err(Error::Truncated),
+            Ordering::Greater => return Err(Error::Corrupted),
+            Ordering::Equal => {}
+        }
+        if !mmap[INDEX_HEADER_LEN..].as_ptr().cast::<usize>().is_aligned() {
+            return Err(Error::Corrupted);
+        }
+
+        let this = Self { words: Words::Mapped(mmap), len, count };
+        // offsets 必须从 0 开始单调递增到 count，否则切片会越界
+        let offsets = this.offsets();
+        if offsets.first() != Some(&0) || offsets.last() != Some(&count) || offsets.windows(2).any(|w| w[0] > w[1]) {
+            return Err(Error::Corrupted);
+        }
+        Ok((this, source))
+    }
+
+    // 先写入临时文件再重命名，其他进程正在映射的旧文件不会被修改
+    pub fn save<P: AsRef<Path>>(&self, path: P, source: u64) -> Result<()> {
+        let path = path.as_ref();
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(format!(".{}.tmp", process::id()));
+        let result = self.write_index(File::create(&tmp)?, source);
+        match result.and_then(|_| Ok(fs::rename(&tmp, path)?)) {
+            Ok(()) => Ok(()),
+            Err(err) => {
+                let _ = fs::remove_file(&tmp);
+                Err(err)
+            }
+        }
+    }
+
+    fn write_index(&self, file: File, source: u64) -> Result<()> {
+        let mut writer = BufWriter::new(file);
+        writer.write_all(&INDEX_MAGIC)?;
+        writer.write_all(&INDEX_VERSION.to_le_bytes())?;
+        writer.write_all(&[mem::size_of::<usize>() as u8, NATIVE_ENDIAN, 0, 0])?;
+        writer.write_all(&source.to_le_bytes())?;
+        writer.write_all(&(self.len as u64).to_le_bytes())?;
+        writer.write_all(&(self.count as u64).to_le_bytes())?;
+        let words = self.words.as_slice();
+        let bytes = unsafe { slice::from_raw_parts(words.as_ptr().cast::<u8>(), mem::size_of_val(words)) };
+        writer.write_all(bytes)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    // 所有储存了指针的地址
+    #[inline]
+    pub fn points(&self) -> &[usize] {
+        &self.words.as_slice()[..self.count]
+    }
+
+    // points 中每个地址储存的指针值
+    #[inline]
+    fn targets(&self) -> &[usize] {
+        &self.words.as_slice()[self.count..self.count * 2]
+    }
+
+    #[inline]
+    fn addrs(&self) -> &[usize] {
+        &self.words.as_slice()[self.count * 2..self.count * 3]
+    }
+
+    #[inline]
+    fn offsets(&self) -> &[usize] {
+        let start = self.count * 3;
+        &self.words.as_slice()[start..start + self.len + 1]
+    }
+
+    #[inline]
+    fn values(&self) -> &[usize] {
+        let start = self.count * 3 + self.len + 1;
+        &self.words.as_slice()[start..start + self.len]
+    }
+
+    // addr 储存的指针值，addr 没有储存指针时为 None
+    #[inline]
+    pub fn read(&self, addr: usize) -> Option<usize> {
+        let idx = self.points().binary_search(&addr).ok()?;
+        Some(self.targets()[idx])
+    }
+
+    // 指针值在 [min, max] 之间的全部 (指针值, 储存了该指针的地址)
+    #[inline]
+    pub fn range(&self, min: usize, max: usize) -> impl Iterator<Item = (usize, &[usize])> {
+        let (values, offsets, addrs) = (self.values(), self.offsets(), self.addrs());
+        let start = values.partition_point(|&v| v < min);
+        let end = values.partition_point(|&v| v <= max).max(start);
+        (start..end).map(move |i| (values[i], &addrs[offsets[i]..offsets[i + 1]]))
+    }
+}
+
+// 按地址排序并去重，保留先出现的指针
+// 指针文件按地址顺序写入，通常已经有序，不需要转换为 (地址, 指针值) 排序
+fn sort_pairs(points: Vec<usize>, targets: Vec<usize>) -> (Vec<usize>, Vec<usize>) {
+    let (mut points, mut targets) = (points, targets);
+    if !points.is_sorted() {
+        let mut pairs = points.into_iter().zip(targets).collect::<Vec<_>>();
+        pairs.sort_by_key(|&(addr, _)| addr);
+        (points, targets) = pairs.into_iter().unzip();
+    }
+    let mut n = 0;
+    for i in 0..points.len() {
+        if n == 0 || points[n - 1] != points[i] {
+            points[n] = points[i];
+            targets[n] = targets[i];
+            n += 1;
+        }
+    }
+    points.truncate(n);
+    targets.truncate(n);
+    (points, targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(graph: &PointerGraph, min: usize, max: usize) -> Vec<(usize, Vec<usize>)> {
+        graph
+            .range(min, max)
+            .map(|(value, addrs)| (value, addrs.to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn build_from_unsorted_pairs() {
+        let points = vec![0x30, 0x10, 0x20, 0x10, 0x40];
+        let targets = vec![0x100, 0x200, 0x100, 0x300, 0x200];
+        let graph = PointerGraph::default().merge(points, targets);
+        assert_eq!(graph.points(), [0x10, 0x20, 0x30, 0x40]);
+        assert_eq!(graph.read(0x10), Some(0x200));
+        assert_eq!(graph.read(0x18), None);
+        assert_eq!(range(&graph, 0, usize::MAX), [(0x100, vec![0x20, 0x30]), (0x200, vec![0x10, 0x40])]);
+        assert_eq!(range(&graph, 0x101, 0x1ff), []);
+    }
+
+    #[test]
+    fn merge_keeps_loaded_pointers() {
+        let graph = PointerGraph::default().merge(vec![0x10, 0x20], vec![0x100, 0x200]);
+        let graph = graph.merge(vec![0x30, 0x20, 0x08], vec![0x100, 0x300, 0x300]);
+        assert_eq!(graph.points(), [0x08, 0x10, 0x20, 0x30]);
+        assert_eq!(graph.read(0x20), Some(0x200));
+        assert_eq!(range(&graph, 0x100, 0x300), [(0x100, vec![0x10, 0x30]), (0x200, vec![0x20]), (0x300, vec![0x08])]);
+        assert!(PointerGraph::default().merge(vec![], vec![]).is_empty());
+    }
+}
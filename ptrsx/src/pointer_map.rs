@@ -1,8 +1,12 @@
-use core::{cmp::Ordering, mem, ops::ControlFlow};
+use core::{cmp::Ordering, ops::ControlFlow};
 
 use vmmap::{VirtualMemoryRead, VirtualQuery};
 
-use super::try_trait::{FromResidual, Try};
+use super::{
+    layout::Layout,
+    progress::Progress,
+    try_trait::{FromResidual, Try},
+};
 
 struct ChunkIter {
     max: usize,
@@ -45,7 +49,7 @@ fn is_pointer<V: VirtualQuery>(addr: &usize, vqs: &[V]) -> bool {
 }
 
 // memory align
-fn _try_pointer_map1<P, V, F, R>(proc: &P, vqs: &[V], f: &mut F) -> R
+fn _try_pointer_map1<P, V, F, R>(proc: &P, vqs: &[V], align: usize, layout: Layout, progress: &Progress, f: &mut F) -> R
 where
     P: VirtualMemoryRead,
     V: VirtualQuery,
@@ -56,14 +60,18 @@ where
     for vq in vqs {
         let (start, size) = (vq.start(), vq.size());
         for (off, size) in ChunkIter::new(size, 0x100000) {
+            if progress.is_cancelled() {
+                return Try::from_output(());
+            }
             if proc.read_exact_at(&mut buf[..size], start + off).is_err() {
                 break;
             }
+            progress.add_bytes(size as u64);
             for (k, v) in buf[..size]
-                .windows(mem::size_of::<usize>())
+                .windows(layout.width())
                 .enumerate()
-                .step_by(mem::size_of::<usize>())
-                .map(|(k, v)| (k, usize::from_ne_bytes(v.try_into().unwrap())))
+                .step_by(align)
+                .map(|(k, v)| (k, layout.read(v)))
                 .filter(|(_, v)| is_pointer(v, vqs))
             {
                 let branch = f(start + off + k, v);
@@ -73,12 +81,13 @@ where
                 }
             }
         }
+        progress.add_region();
     }
     Try::from_output(())
 }
 
 // memory not align
-fn _try_pointer_map2<P, V, F, R>(proc: &P, vqs: &[V], f: &mut F) -> R
+fn _try_pointer_map2<P, V, F, R>(proc: &P, vqs: &[V], layout: Layout, progress: &Progress, f: &mut F) -> R
 where
     P: VirtualMemoryRead,
     V: VirtualQuery,
@@ -89,13 +98,17 @@ where
     for vq in vqs {
         let (start, size) = (vq.start(), vq.size());
         for (off, size) in ChunkIter::new(size, 0x100000) {
+            if progress.is_cancelled() {
+                return Try::from_output(());
+            }
             if proc.read_exact_at(&mut buf[..size], start + off).is_err() {
                 break;
             }
+            progress.add_bytes(size as u64);
             for (k, v) in buf[..size]
-                .windows(mem::size_of::<usize>())
+                .windows(layout.width())
                 .enumerate()
-                .map(|(k, v)| (k, usize::from_ne_bytes(v.try_into().unwrap())))
+                .map(|(k, v)| (k, layout.read(v)))
                 .filter(|(_, v)| is_pointer(v, vqs))
             {
                 let branch = f(start + off + k, v);
@@ -105,12 +118,13 @@ where
                 }
             }
         }
+        progress.add_region();
     }
     Try::from_output(())
 }
 
 // memory align
-fn _pointer_map1<P, V, F>(proc: &P, vqs: &[V], f: &mut F)
+fn _pointer_map1<P, V, F>(proc: &P, vqs: &[V], align: usize, layout: Layout, f: &mut F)
 where
     P: VirtualMemoryRead,
     V: VirtualQuery,
@@ -124,10 +138,10 @@ where
                 break;
             }
             for (k, v) in buf[..size]
-                .windows(mem::size_of::<usize>())
+                .windows(layout.width())
                 .enumerate()
-                .step_by(mem::size_of::<usize>())
-                .map(|(k, v)| (k, usize::from_ne_bytes(v.try_into().unwrap())))
+                .step_by(align)
+                .map(|(k, v)| (k, layout.read(v)))
                 .filter(|(_, v)| is_pointer(v, vqs))
             {
                 f(start + off + k, v)
@@ -137,7 +151,7 @@ where
 }
 
 // memory not align
-fn _pointer_map2<P, V, F>(proc: &P, vqs: &[V], f: &mut F)
+fn _pointer_map2<P, V, F>(proc: &P, vqs: &[V], layout: Layout, f: &mut F)
 where
     P: VirtualMemoryRead,
     V: VirtualQuery,
@@ -151,9 +165,9 @@ where
                 break;
             }
             for (k, v) in buf[..size]
-                .windows(mem::size_of::<usize>())
+                .windows(layout.width())
                 .enumerate()
-                .map(|(k, v)| (k, usize::from_ne_bytes(v.try_into().unwrap())))
+                .map(|(k, v)| (k, layout.read(v)))
                 .filter(|(_, v)| is_pointer(v, vqs))
             {
                 f(start + off + k, v)
@@ -164,27 +178,37 @@ where
 
 // TODO: maybe make public
 #[allow(dead_code)]
-fn create_pointer_map<P, V, F>(proc: &P, vqs: &[V], align: bool, f: &mut F)
+fn create_pointer_map<P, V, F>(proc: &P, vqs: &[V], align: usize, layout: Layout, f: &mut F)
 where
     P: VirtualMemoryRead,
     V: VirtualQuery,
     F: FnMut(usize, usize),
 {
     match align {
-        true => _pointer_map1(proc, vqs, f),
-        false => _pointer_map2(proc, vqs, f),
+        1 => _pointer_map2(proc, vqs, layout, f),
+        n => _pointer_map1(proc, vqs, n, layout, f),
     }
 }
 
-pub fn try_create_pointer_map<P, V, F, R>(proc: &P, vqs: &[V], align: bool, f: &mut F) -> R
+// 取消时提前返回 from_output，由调用者检查 progress 是否已取消
+pub fn try_create_pointer_map<P, V, F, R>(
+    proc: &P,
+    vqs: &[V],
+    align: usize,
+    layout: Layout,
+    progress: &Progress,
+    f: &mut F,
+) -> R
 where
     P: VirtualMemoryRead,
     V: VirtualQuery,
     F: FnMut(usize, usize) -> R,
     R: Try<Output = ()>,
 {
+    let total = vqs.iter().map(|vq| vq.size() as u64).sum();
+    progress.add_total(total, vqs.len() as u64);
     match align {
-        true => _try_pointer_map1(proc, vqs, f),
-        false => _try_pointer_map2(proc, vqs, f),
+        1 => _try_pointer_map2(proc, vqs, layout, progress, f),
+        n => _try_pointer_map1(proc, vqs, n, layout, progress, f),
     }
 }
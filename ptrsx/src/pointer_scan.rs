@@ -1,20 +1,66 @@
 use core::{
     iter,
-    ops::{Bound, ControlFlow},
+    ops::ControlFlow,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    sync::{
+        mpsc::{self, SyncSender},
+        Condvar, Mutex, MutexGuard, PoisonError,
+    },
+    thread,
 };
-use std::collections::BTreeMap;
 
-use super::try_trait::{FromResidual, Try};
+use super::{
+    offset_pattern::OffsetPattern,
+    pointer_graph::PointerGraph,
+    progress::Progress,
+    try_trait::{FromResidual, Try},
+};
 
-pub struct Param {
+#[derive(Clone, Copy)]
+pub struct Param<'a> {
     pub depth: usize,
+    // 只输出长度不小于 min_depth 的指针链
+    pub min_depth: usize,
     pub addr: usize,
     pub range: (usize, usize),
+    // 每层偏移的约束，在遍历时剪枝
+    pub pattern: &'a OffsetPattern,
+    // 每个偏移都必须是 align 的倍数，0 和 1 表示不限制
+    pub align: usize,
+}
+
+impl Param<'_> {
+    // 第 curr 层指向 addr 附近的全部 (指针值, 储存了该指针的地址)
+    // 已经按 range、pattern 和 align 过滤
+    #[inline]
+    fn children<'g>(
+        &self,
+        graph: &'g PointerGraph,
+        addr: usize,
+        curr: usize,
+    ) -> impl Iterator<Item = (usize, &'g [usize])> {
+        let Param { range, pattern, align, .. } = *self;
+        let (lo, hi) = pattern.rule(curr).map_or((1, 0), |r| r.bounds());
+        let lo = (lo as i128).max(-(range.0 as i128));
+        let hi = (hi as i128).min(range.1 as i128);
+        let clamp = |n: i128| n.clamp(0, usize::MAX as i128) as usize;
+        let (min, max) = match lo <= hi {
+            true => (clamp(addr as i128 - hi), clamp(addr as i128 - lo)),
+            false => (1, 0),
+        };
+        graph
+            .range(min, max)
+            .filter(move |&(k, _)| align <= 1 || (addr.wrapping_sub(k) as isize) % align as isize == 0)
+    }
 }
 
 pub struct Chain<'a> {
     addr: usize,
-    data: &'a [(usize, isize)],
+    data: Cow<'a, [(usize, isize)]>,
 }
 
 impl Chain<'_> {
@@ -32,10 +78,15 @@ impl Chain<'_> {
 
     // 获取指针链长度
     #[inline]
-    pub const fn len(&self) -> usize {
+    pub fn len(&self) -> usize {
         self.data.len()
     }
 
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
     // // 获取指针链第一个偏移
     // #[inline]
     // pub fn first(&self) -> Option<&isize> {
@@ -56,22 +107,39 @@ impl Chain<'_> {
         let n = rest.iter().position(|x| x.0 == first.0)?;
         Some(iter::once(first).chain(rest.iter().skip(n + 1)).rev().map(|(_, o)| o))
     }
+
+    // 去除循环引用，不存在循环引用时原样返回
+    #[inline]
+    pub fn strip_cycle(self) -> Self {
+        let Some((first, rest)) = self.data.split_first() else {
+            return self;
+        };
+        let Some(n) = rest.iter().position(|x| x.0 == first.0) else {
+            return self;
+        };
+        let data = iter::once(first).chain(&rest[n + 1..]).copied().collect();
+        Self { addr: self.addr, data: Cow::Owned(data) }
+    }
 }
 
 // large amounts data
-fn _try_chain_scan_1<F, R>(map: &BTreeMap<usize, Vec<usize>>, points: &[usize], param: Param, f: &mut F) -> R
+fn _try_chain_scan_1<F, R>(graph: &PointerGraph, points: &[usize], param: Param, progress: &Progress, f: &mut F) -> R
 where
     F: FnMut(Chain) -> R,
     R: Try<Output = ()>,
 {
     let mut data = Vec::with_capacity(param.depth);
-    __try_chain_scan_1(map, points, param, f, &mut data, 0)
+    __try_chain_scan_1(graph, points, param, progress, &AtomicBool::new(false), f, &mut data, 0)
 }
 
+// stop 与取消相同，但只结束这一次遍历，用于多线程扫描提前结束
+#[allow(clippy::too_many_arguments)]
 fn __try_chain_scan_1<F, R>(
-    map: &BTreeMap<usize, Vec<usize>>,
+    graph: &PointerGraph,
     points: &[usize],
     param: Param,
+    progress: &Progress,
+    stop: &AtomicBool,
     f: &mut F,
     data: &mut Vec<(usize, isize)>,
     curr: usize,
@@ -80,20 +148,27 @@ where
     F: FnMut(Chain) -> R,
     R: Try<Output = ()>,
 {
-    let Param { depth, addr, range } = param;
+    if progress.is_cancelled() || stop.load(Ordering::Relaxed) {
+        return Try::from_output(());
+    }
+    progress.add_node();
+
+    let Param { depth, min_depth, addr, range, pattern, .. } = param;
     let min = addr.saturating_sub(range.1);
     let max = addr.saturating_add(range.0);
 
     let idx = points.binary_search(&min).unwrap_or_else(|x| x);
 
-    if points
-        .iter()
-        .skip(idx)
-        .take_while(|x| max.ge(x))
-        .min_by_key(|x| (x.wrapping_sub(addr) as isize).abs())
-        .is_some()
+    if curr >= min_depth
+        && pattern.accepts(curr)
+        && points
+            .iter()
+            .skip(idx)
+            .take_while(|x| max.ge(x))
+            .min_by_key(|x| (x.wrapping_sub(addr) as isize).abs())
+            .is_some()
     {
-        let branch = f(Chain { addr, data });
+        let branch = f(Chain { addr, data: Cow::Borrowed(data) });
         match Try::branch(branch) {
             ControlFlow::Continue(c) => c,
             ControlFlow::Break(b) => return FromResidual::from_residual(b),
@@ -101,14 +176,21 @@ where
     }
 
     if curr < depth {
-        for (&k, v) in map.range((Bound::Included(min), Bound::Included(max))) {
+        if curr == 0 {
+            progress.add_total_branches(param.children(graph, addr, 0).map(|(_, v)| v.len() as u64).sum());
+        }
+        for (k, v) in param.children(graph, addr, curr) {
             data.push((k, addr.wrapping_sub(k) as isize));
             for &addr in v {
-                let branch = __try_chain_scan_1(map, points, Param { depth, addr, range }, f, data, curr + 1);
+                let param = Param { addr, ..param };
+                let branch = __try_chain_scan_1(graph, points, param, progress, stop, f, data, curr + 1);
                 match Try::branch(branch) {
                     ControlFlow::Continue(c) => c,
                     ControlFlow::Break(b) => return FromResidual::from_residual(b),
                 }
+                if curr == 0 && !progress.is_cancelled() {
+                    progress.add_branch();
+                }
             }
             data.pop();
         }
@@ -118,19 +200,20 @@ where
 }
 
 // small amount data
-fn _try_chain_scan_2<F, R>(map: &BTreeMap<usize, Vec<usize>>, points: &[usize], param: Param, f: &mut F) -> R
+fn _try_chain_scan_2<F, R>(graph: &PointerGraph, points: &[usize], param: Param, progress: &Progress, f: &mut F) -> R
 where
     F: FnMut(Chain) -> R,
     R: Try<Output = ()>,
 {
     let mut data = Vec::with_capacity(param.depth);
-    __try_chain_scan_2(map, points, param, f, &mut data, 0)
+    __try_chain_scan_2(graph, points, param, progress, f, &mut data, 0)
 }
 
 fn __try_chain_scan_2<F, R>(
-    map: &BTreeMap<usize, Vec<usize>>,
+    graph: &PointerGraph,
     points: &[usize],
     param: Param,
+    progress: &Progress,
     f: &mut F,
     data: &mut Vec<(usize, isize)>,
     curr: usize,
@@ -139,20 +222,27 @@ where
     F: FnMut(Chain) -> R,
     R: Try<Output = ()>,
 {
-    let Param { depth, addr, range } = param;
+    if progress.is_cancelled() {
+        return Try::from_output(());
+    }
+    progress.add_node();
+
+    let Param { depth, min_depth, addr, range, pattern, .. } = param;
     let min = addr.saturating_sub(range.1);
     let max = addr.saturating_add(range.0);
 
     let idx = points.iter().position(|x| min.le(x)).unwrap_or(points.len());
 
-    if points
-        .iter()
-        .skip(idx)
-        .take_while(|x| max.ge(x))
-        .min_by_key(|x| (x.wrapping_sub(addr) as isize).abs())
-        .is_some()
+    if curr >= min_depth
+        && pattern.accepts(curr)
+        && points
+            .iter()
+            .skip(idx)
+            .take_while(|x| max.ge(x))
+            .min_by_key(|x| (x.wrapping_sub(addr) as isize).abs())
+            .is_some()
     {
-        let branch = f(Chain { addr, data });
+        let branch = f(Chain { addr, data: Cow::Borrowed(data) });
         match Try::branch(branch) {
             ControlFlow::Continue(c) => c,
             ControlFlow::Break(b) => return FromResidual::from_residual(b),
@@ -160,14 +250,20 @@ where
     }
 
     if curr < depth {
-        for (&k, v) in map.range((Bound::Included(min), Bound::Included(max))) {
+        if curr == 0 {
+            progress.add_total_branches(param.children(graph, addr, 0).map(|(_, v)| v.len() as u64).sum());
+        }
+        for (k, v) in param.children(graph, addr, curr) {
             data.push((k, addr.wrapping_sub(k) as isize));
             for &addr in v {
-                let branch = __try_chain_scan_2(map, points, Param { depth, addr, range }, f, data, curr + 1);
+                let branch = __try_chain_scan_2(graph, points, Param { addr, ..param }, progress, f, data, curr + 1);
                 match Try::branch(branch) {
                     ControlFlow::Continue(c) => c,
                     ControlFlow::Break(b) => return FromResidual::from_residual(b),
                 }
+                if curr == 0 && !progress.is_cancelled() {
+                    progress.add_branch();
+                }
             }
             data.pop();
         }
@@ -176,15 +272,430 @@ where
 }
 
 // TODO: 根据一些规则调优，s1和s2有巨大的性能差距
-pub fn try_pointer_chain_scan<F, R>(map: &BTreeMap<usize, Vec<usize>>, points: &[usize], param: Param, f: &mut F) -> R
+// 取消时提前返回 from_output，由调用者检查 progress 是否已取消
+pub fn try_pointer_chain_scan<F, R>(
+    graph: &PointerGraph,
+    points: &[usize],
+    param: Param,
+    progress: &Progress,
+    f: &mut F,
+) -> R
 where
     F: FnMut(Chain) -> R,
     R: Try<Output = ()>,
 {
     // let count = map.values().filter(|v| v.len() < 64).count();
     // match (map.len() - count).checked_mul(128) {
-    //     Some(n) if n < count => _try_chain_scan_2(map, points, param, f),
-    //     _ => _try_chain_scan_1(map, points, param, f),
+    //     Some(n) if n < count => _try_chain_scan_2(graph, points, param, f),
+    //     _ => _try_chain_scan_1(graph, points, param, f),
     // }
-    _try_chain_scan_1(map, points, param, f)
+    _try_chain_scan_1(graph, points, param, progress, f)
+}
+
+// 每个线程一次发送的指针链数量
+const BATCH_SIZE: usize = 0x1000;
+
+// 第一层子树，(k, 偏移, 下一层地址)
+type Task = (usize, isize, usize);
+// (子树序号, 指针链, 子树是否结束)
+type Batch = (usize, Vec<(usize, Vec<(usize, isize)>)>, bool);
+
+// 按第一层子树拆分后多线程扫描，输出顺序与单线程扫描完全相同
+// f 总是在调用线程中执行，最多同时有 threads * 4 个子树在扫描或等待输出
+pub fn try_pointer_chain_scan_par<F, R>(
+    graph: &PointerGraph,
+    points: &[usize],
+    param: Param,
+    threads: usize,
+    progress: &Progress,
+    f: &mut F,
+) -> R
+where
+    F: FnMut(Chain) -> R,
+    R: Try<Output = ()>,
+{
+    let Param { depth, addr, .. } = param;
+    if threads <= 1 || depth == 0 {
+        return try_pointer_chain_scan(graph, points, param, progress, f);
+    }
+
+    // 基址本身
+    let base = Param { depth: 0, ..param };
+    let branch = __try_chain_scan_1(graph, points, base, progress, &AtomicBool::new(false), f, &mut Vec::new(), 0);
+    match Try::branch(branch) {
+        ControlFlow::Continue(c) => c,
+        ControlFlow::Break(b) => return FromResidual::from_residual(b),
+    }
+
+    let tasks = param
+        .children(graph, addr, 0)
+        .flat_map(|(k, v)| v.iter().map(move |&a| (k, addr.wrapping_sub(k) as isize, a)))
+        .collect::<Vec<Task>>();
+    try_scan_tasks(graph, points, param, &tasks, threads, progress, &mut |_, chain| f(chain))
+}
+
+// 依次扫描每个第一层子树，f 的第一个参数是子树序号，输出顺序与 tasks 顺序相同
+fn try_scan_tasks<F, R>(
+    graph: &PointerGraph,
+    points: &[usize],
+    param: Param,
+    tasks: &[Task],
+    threads: usize,
+    progress: &Progress,
+    f: &mut F,
+) -> R
+where
+    F: FnMut(usize, Chain) -> R,
+    R: Try<Output = ()>,
+{
+    progress.add_total_branches(tasks.len() as u64);
+    if threads <= 1 {
+        let mut data = Vec::with_capacity(param.depth);
+        let stop = AtomicBool::new(false);
+        for (idx, &(k, offset, addr)) in tasks.iter().enumerate() {
+            data.clear();
+            data.push((k, offset));
+            let param = Param { addr, ..param };
+            let mut g = |chain: Chain| f(idx, chain);
+            let branch = __try_chain_scan_1(graph, points, param, progress, &stop, &mut g, &mut data, 1);
+            match Try::branch(branch) {
+                ControlFlow::Continue(c) => c,
+                ControlFlow::Break(b) => return FromResidual::from_residual(b),
+            }
+            if progress.is_cancelled() {
+                break;
+            }
+            progress.add_branch();
+        }
+        return Try::from_output(());
+    }
+
+    let window = threads * 4;
+    let next = AtomicUsize::new(0);
+    let schedule = Schedule::new(progress);
+
+    thread::scope(|s| {
+        let (tx, rx) = mpsc::sync_channel::<Batch>(window);
+        for _ in 0..threads.min(tasks.len()) {
+            let tx = tx.clone();
+            let (next, schedule) = (&next, &schedule);
+            let worker = Worker { graph, points, param, progress, tasks, next, schedule, window };
+            s.spawn(move || worker.run(tx));
+        }
+        drop(tx);
+
+        let mut pending = BTreeMap::<usize, (Vec<Vec<(usize, Vec<(usize, isize)>)>>, bool)>::new();
+        let mut curr = 0;
+        let result = 'outer: loop {
+            let Ok((idx, chains, done)) = rx.recv() else {
+                break ControlFlow::Continue(());
+            };
+            let entry = pending.entry(idx).or_default();
+            entry.0.push(chains);
+            entry.1 |= done;
+
+            // 按子树顺序输出，后面的子树先缓存
+            while let Some((batches, done)) = pending.get_mut(&curr) {
+                for batch in batches.drain(..) {
+                    let size = batch.len();
+                    for (addr, data) in batch {
+                        let branch = f(curr, Chain { addr, data: Cow::Owned(data) });
+                        if let ControlFlow::Break(b) = Try::branch(branch) {
+                            break 'outer ControlFlow::Break(b);
+                        }
+                    }
+                    schedule.release(size);
+                }
+                if !*done {
+                    break;
+                }
+                pending.remove(&curr);
+                curr += 1;
+                schedule.advance(curr);
+            }
+        };
+        schedule.stop();
+        drop(rx);
+        match result {
+            ControlFlow::Continue(_) => R::from_output(()),
+            ControlFlow::Break(b) => FromResidual::from_residual(b),
+        }
+    })
+}
+
+// 多线程扫描时最多缓存的指针链数量，超过后只有正在输出的子树可以继续
+const MAX_BUFFERED: usize = BATCH_SIZE * 64;
+
+// 多线程扫描的调度状态，工作线程在条件不满足时等待，不轮询
+struct Schedule<'a> {
+    progress: &'a Progress,
+    state: Mutex<ScheduleState>,
+    cond: Condvar,
+    // 输出已经结束，工作线程在遍历中也会检查
+    stop: AtomicBool,
+}
+
+#[derive(Default)]
+struct ScheduleState {
+    // 正在输出的子树序号
+    cursor: usize,
+    // 已经发送但还没有输出的指针链数量
+    buffered: usize,
+}
+
+impl<'a> Schedule<'a> {
+    fn new(progress: &'a Progress) -> Self {
+        Self {
+            progress,
+            state: Mutex::default(),
+            cond: Condvar::new(),
+            stop: AtomicBool::new(false),
+        }
+    }
+
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, ScheduleState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // 等待 ready 成立，已经停止或取消时返回 None
+    // 取消时正在输出的子树会很快结束并推进 cursor，等待的线程随之被唤醒
+    fn wait(&self, ready: impl Fn(&ScheduleState) -> bool) -> Option<MutexGuard<'_, ScheduleState>> {
+        let mut state = self.lock();
+        loop {
+            if self.stop.load(Ordering::Acquire) || self.progress.is_cancelled() {
+                return None;
+            }
+            if ready(&state) {
+                return Some(state);
+            }
+            state = self.cond.wait(state).unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    // 避免领先输出太多子树
+    fn wait_window(&self, idx: usize, window: usize) -> bool {
+        self.wait(|s| idx < s.cursor + window).is_some()
+    }
+
+    // 占用 size 条缓存，正在输出的子树不需要等待，否则会死锁
+    fn reserve(&self, idx: usize, size: usize) -> bool {
+        match self.wait(|s| idx <= s.cursor || s.buffered + size <= MAX_BUFFERED) {
+            Some(mut state) => {
+                state.buffered += size;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn release(&self, size: usize) {
+        let mut state = self.lock();
+        state.buffered -= size;
+        drop(state);
+        self.cond.notify_all();
+    }
+
+    fn advance(&self, cursor: usize) {
+        self.lock().cursor = cursor;
+        self.cond.notify_all();
+    }
+
+    fn stop(&self) {
+        let _state = self.lock();
+        self.stop.store(true, Ordering::Release);
+        self.cond.notify_all();
+    }
+}
+
+struct Worker<'a> {
+    graph: &'a PointerGraph,
+    points: &'a [usize],
+    param: Param<'a>,
+    progress: &'a Progress,
+    tasks: &'a [Task],
+    next: &'a AtomicUsize,
+    schedule: &'a Schedule<'a>,
+    window: usize,
+}
+
+impl Worker<'_> {
+    fn run(self, tx: SyncSender<Batch>) {
+        let Self { graph, points, param, progress, tasks, next, schedule, window } = self;
+        let mut data = Vec::with_capacity(param.depth);
+        loop {
+            let idx = next.fetch_add(1, Ordering::Relaxed);
+            let Some(&(k, offset, addr)) = tasks.get(idx) else {
+                return;
+            };
+            if !schedule.wait_window(idx, window) {
+                return;
+            }
+
+            let mut chains = Vec::new();
+            let mut f = |chain: Chain| {
+                chains.push((chain.addr, chain.data.into_owned()));
+                if chains.len() >= BATCH_SIZE {
+                    let batch = core::mem::take(&mut chains);
+                    if !schedule.reserve(idx, batch.len()) || tx.send((idx, batch, false)).is_err() {
+                        return ControlFlow::Break(());
+                    }
+                }
+                ControlFlow::Continue(())
+            };
+
+            data.clear();
+            data.push((k, offset));
+            let param = Param { addr, ..param };
+            let stop = &schedule.stop;
+            if __try_chain_scan_1(graph, points, param, progress, stop, &mut f, &mut data, 1).is_break() {
+                return;
+            }
+            if schedule.stop.load(Ordering::Acquire) {
+                return;
+            }
+            if !progress.is_cancelled() {
+                progress.add_branch();
+            }
+            // 子树的最后一批不超过 BATCH_SIZE，不等待缓存
+            schedule.lock().buffered += chains.len();
+            if tx.send((idx, chains, true)).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+// 迭代加深，按长度从短到长依次输出指针链，同一长度内的顺序与普通扫描相同
+// 每一轮都重新遍历较浅的层，代价通常远小于最深一层
+pub fn try_pointer_chain_scan_shortest<F, R>(
+    graph: &PointerGraph,
+    points: &[usize],
+    param: Param,
+    threads: usize,
+    progress: &Progress,
+    f: &mut F,
+) -> R
+where
+    F: FnMut(Chain) -> R,
+    R: Try<Output = ()>,
+{
+    for depth in param.min_depth..=param.depth {
+        let param = Param { depth, min_depth: depth, ..param };
+        let branch = try_pointer_chain_scan_par(graph, points, param, threads, progress, f);
+        match Try::branch(branch) {
+            ControlFlow::Continue(c) => c,
+            ControlFlow::Break(b) => return FromResidual::from_residual(b),
+        }
+        if progress.is_cancelled() {
+            break;
+        }
+    }
+    Try::from_output(())
+}
+
+// 多个目标地址共享同一次遍历，f 的第一个参数是目标地址在 addrs 中的序号
+// 第一层之后的子树与目标地址无关，多个目标地址附近的同一个指针只扫描一次
+// 每个目标地址的输出顺序与单独扫描该地址时相同，param.addr 被忽略
+pub fn try_pointer_chain_scan_multi<F, R>(
+    graph: &PointerGraph,
+    points: &[usize],
+    param: Param,
+    addrs: &[usize],
+    threads: usize,
+    progress: &Progress,
+    f: &mut F,
+) -> R
+where
+    F: FnMut(usize, Chain) -> R,
+    R: Try<Output = ()>,
+{
+    let Param { depth, .. } = param;
+
+    // 基址本身
+    for (i, &addr) in addrs.iter().enumerate() {
+        let param = Param { depth: 0, addr, ..param };
+        let mut g = |chain: Chain| f(i, chain);
+        let branch =
+            __try_chain_scan_1(graph, points, param, progress, &AtomicBool::new(false), &mut g, &mut Vec::new(), 0);
+        match Try::branch(branch) {
+            ControlFlow::Continue(c) => c,
+            ControlFlow::Break(b) => return FromResidual::from_residual(b),
+        }
+    }
+    if depth == 0 {
+        return Try::from_output(());
+    }
+
+    let groups = first_level_groups(graph, param, addrs);
+    let (tasks, owners): (Vec<Task>, Vec<usize>) = groups
+        .iter()
+        .enumerate()
+        .flat_map(|(n, &(k, _))| graph.range(k, k).flat_map(|(_, v)| v).map(move |&a| ((k, 0, a), n)))
+        .unzip();
+
+    let mut g = |idx: usize, chain: Chain| {
+        let (k, targets) = &groups[owners[idx]];
+        for &i in targets {
+            // 第一个元素是最后一个偏移，替换成相对于该目标地址的偏移
+            let mut data = chain.data.to_vec();
+            data[0].1 = addrs[i].wrapping_sub(*k) as isize;
+            let branch = f(i, Chain { addr: chain.addr, data: Cow::Owned(data) });
+            match Try::branch(branch) {
+                ControlFlow::Continue(c) => c,
+                ControlFlow::Break(b) => return FromResidual::from_residual(b),
+            }
+        }
+        Try::from_output(())
+    };
+    try_scan_tasks(graph, points, param, &tasks, threads, progress, &mut g)
+}
+
+// 关联扫描，只扫描同时在所有目标地址范围内的第一层指针，即同一个对象的多个字段
+// 输出指向 addrs[0] 的指针链，其他目标地址只有最后一个偏移不同
+// 不包含长度为 0 的指针链
+pub fn try_pointer_chain_scan_fields<F, R>(
+    graph: &PointerGraph,
+    points: &[usize],
+    param: Param,
+    addrs: &[usize],
+    threads: usize,
+    progress: &Progress,
+    f: &mut F,
+) -> R
+where
+    F: FnMut(Chain) -> R,
+    R: Try<Output = ()>,
+{
+    let Param { depth, .. } = param;
+    let Some(&first) = addrs.first() else {
+        return Try::from_output(());
+    };
+    if depth == 0 {
+        return Try::from_output(());
+    }
+
+    // 只保留在所有目标地址范围内的第一层指针
+    let tasks = first_level_groups(graph, param, addrs)
+        .into_iter()
+        .filter(|(_, targets)| targets.len() == addrs.len())
+        .flat_map(|(k, _)| {
+            graph
+                .range(k, k)
+                .flat_map(|(_, v)| v)
+                .map(move |&a| (k, first.wrapping_sub(k) as isize, a))
+        })
+        .collect::<Vec<Task>>();
+    try_scan_tasks(graph, points, param, &tasks, threads, progress, &mut |_, chain| f(chain))
+}
+
+// 第一层的每个指针，以及它在哪些目标地址的范围内
+fn first_level_groups(graph: &PointerGraph, param: Param, addrs: &[usize]) -> Vec<(usize, Vec<usize>)> {
+    let mut groups = BTreeMap::<usize, Vec<usize>>::new();
+    for (i, &addr) in addrs.iter().enumerate() {
+        param
+            .children(graph, addr, 0)
+            .for_each(|(k, _)| groups.entry(k).or_default().push(i));
+    }
+    groups.into_iter().collect()
 }
@@ -0,0 +1,104 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc,
+};
+
+#[derive(Default)]
+struct Inner {
+    bytes: AtomicU64,
+    total_bytes: AtomicU64,
+    regions: AtomicU64,
+    total_regions: AtomicU64,
+    branches: AtomicU64,
+    total_branches: AtomicU64,
+    nodes: AtomicU64,
+    chains: AtomicU64,
+    cancelled: AtomicBool,
+}
+
+// 创建指针文件和扫描指针链的进度，克隆后共享同一份数据，
+// 可以在其他线程读取进度或取消
+#[derive(Clone, Default)]
+pub struct Progress(Arc<Inner>);
+
+// 某一时刻的进度
+// bytes/regions 用于创建指针文件，branches/nodes/chains 用于扫描指针链
+// branches 是基址下第一层已经扫描完成的分支
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ProgressState {
+    pub bytes: u64,
+    pub total_bytes: u64,
+    pub regions: u64,
+    pub total_regions: u64,
+    pub branches: u64,
+    pub total_branches: u64,
+    pub nodes: u64,
+    pub chains: u64,
+}
+
+impl Progress {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 请求停止，正在运行的任务会尽快结束并返回 Error::Cancelled
+    #[inline]
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn state(&self) -> ProgressState {
+        let inner = &self.0;
+        ProgressState {
+            bytes: inner.bytes.load(Ordering::Relaxed),
+            total_bytes: inner.total_bytes.load(Ordering::Relaxed),
+            regions: inner.regions.load(Ordering::Relaxed),
+            total_regions: inner.total_regions.load(Ordering::Relaxed),
+            branches: inner.branches.load(Ordering::Relaxed),
+            total_branches: inner.total_branches.load(Ordering::Relaxed),
+            nodes: inner.nodes.load(Ordering::Relaxed),
+            chains: inner.chains.load(Ordering::Relaxed),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn add_total(&self, bytes: u64, regions: u64) {
+        self.0.total_bytes.fetch_add(bytes, Ordering::Relaxed);
+        self.0.total_regions.fetch_add(regions, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn add_bytes(&self, n: u64) {
+        self.0.bytes.fetch_add(n, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn add_region(&self) {
+        self.0.regions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn add_total_branches(&self, n: u64) {
+        self.0.total_branches.fetch_add(n, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn add_branch(&self) {
+        self.0.branches.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn add_node(&self) {
+        self.0.nodes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn add_chain(&self) {
+        self.0.chains.fetch_add(1, Ordering::Relaxed);
+    }
+}
@@ -37,6 +37,10 @@ impl<K, V> RangeMap<K, V> {
     pub fn clear(&mut self) {
         self.0.clear()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
 }
 
 impl<K, V> RangeMap<K, V>
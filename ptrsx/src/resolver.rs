@@ -0,0 +1,166 @@
+use core::{fmt, ops::Range};
+use std::collections::HashMap;
+
+use vmmap::{VirtualMemoryRead, VirtualQuery};
+
+use super::{base_modules, Error, Layout, ModuleBase, PointerChain, ProcessInfo};
+
+// 指针链解析失败的原因，step 是偏移的序号，从 0 开始
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolveError {
+    // 进程中没有这个模块
+    UnknownModule(String),
+    // 第 step 步无法读取 addr
+    Unreadable { step: usize, addr: usize },
+    // 第 step 步在 addr 读取到空指针
+    NullPointer { step: usize, addr: usize },
+    // 第 step 步计算地址溢出，基址溢出时 step 为 None
+    Overflow { step: Option<usize> },
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolveError::UnknownModule(name) => write!(f, "unknown module: {name}"),
+            ResolveError::Unreadable { step, addr } => write!(f, "step {step}: cannot read address {addr:#x}"),
+            ResolveError::NullPointer { step, addr } => write!(f, "step {step}: null pointer at {addr:#x}"),
+            ResolveError::Overflow { step: Some(step) } => write!(f, "step {step}: address overflow"),
+            ResolveError::Overflow { step: None } => write!(f, "base address overflow"),
+        }
+    }
+}
+
+impl std::error::Error for ResolveError {}
+
+// 内存区域，name 为 None 时是匿名区域
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub range: Range<usize>,
+    pub name: Option<String>,
+}
+
+// 解析指针链的一步，从 addr 读取 value，加上 offset 得到 result
+// region 是 result 所在的内存区域，不在任何区域中时为 None
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolveStep {
+    pub addr: usize,
+    pub value: usize,
+    pub offset: isize,
+    pub result: usize,
+    pub region: Option<Region>,
+}
+
+// 指针链的解析过程，base 是模块基址加上偏移
+// 中途失败时 steps 是失败之前完成的步骤，error 是失败的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolveTrace {
+    pub base: usize,
+    pub steps: Vec<ResolveStep>,
+    pub error: Option<ResolveError>,
+}
+
+impl ResolveTrace {
+    // 最终得到的地址
+    pub fn target(&self) -> Result<usize, ResolveError> {
+        match &self.error {
+            Some(err) => Err(err.clone()),
+            None => Ok(self.steps.last().map_or(self.base, |s| s.result)),
+        }
+    }
+}
+
+// 在进程中解析指针链，创建时记录模块基址和内存区域，
+// 进程的内存映射变化后需要重新创建
+pub struct ChainResolver {
+    modules: HashMap<String, usize>,
+    regions: Vec<Region>,
+}
+
+impl ChainResolver {
+    pub fn new<P: ProcessInfo>(proc: &P, base: ModuleBase) -> Result<Self, Error> {
+        let maps = proc.get_maps().collect::<Result<Vec<_>, vmmap::Error>>()?;
+        let modules = base_modules(proc, &maps, base)
+            .into_iter()
+            .map(|(range, name)| (name, range.start))
+            .collect();
+        let mut regions = maps
+            .iter()
+            .map(|x| Region { range: x.start()..x.end(), name: x.name().map(String::from) })
+            .collect::<Vec<_>>();
+        regions.sort_unstable_by_key(|x| x.range.start);
+        Ok(Self { modules, regions })
+    }
+
+    // 模块基址
+    pub fn module(&self, name: &str) -> Option<usize> {
+        self.modules.get(name).copied()
+    }
+
+    // addr 所在的内存区域
+    pub fn region(&self, addr: usize) -> Option<&Region> {
+        let idx = self.regions.partition_point(|x| x.range.start <= addr).checked_sub(1)?;
+        let region = &self.regions[idx];
+        region.range.contains(&addr).then_some(region)
+    }
+
+    pub fn resolve<P: VirtualMemoryRead>(
+        &self,
+        proc: &P,
+        layout: Layout,
+        chain: &PointerChain,
+    ) -> Result<usize, ResolveError> {
+        self.walk(proc, layout, chain, |_, _, _, _| {})
+    }
+
+    // 只有基址无法计算时返回错误，之后的失败记录在 ResolveTrace::error 中
+    pub fn trace<P: VirtualMemoryRead>(
+        &self,
+        proc: &P,
+        layout: Layout,
+        chain: &PointerChain,
+    ) -> Result<ResolveTrace, ResolveError> {
+        let base = self.base(chain)?;
+        let mut steps = Vec::with_capacity(chain.offsets.len());
+        let mut f = |addr, value, offset, result| {
+            let region = self.region(result).cloned();
+            steps.push(ResolveStep { addr, value, offset, result, region });
+        };
+        let error = self.walk(proc, layout, chain, &mut f).err();
+        Ok(ResolveTrace { base, steps, error })
+    }
+
+    fn base(&self, chain: &PointerChain) -> Result<usize, ResolveError> {
+        let base = match &chain.module {
+            Some(name) => self
+                .module(name)
+                .ok_or_else(|| ResolveError::UnknownModule(name.clone()))?,
+            None => 0,
+        };
+        base.checked_add(chain.offset)
+            .ok_or(ResolveError::Overflow { step: None })
+    }
+
+    // f 的参数为 (读取的地址, 读取到的指针, 偏移, 结果)
+    fn walk<P, F>(&self, proc: &P, layout: Layout, chain: &PointerChain, mut f: F) -> Result<usize, ResolveError>
+    where
+        P: VirtualMemoryRead,
+        F: FnMut(usize, usize, isize, usize),
+    {
+        let mut addr = self.base(chain)?;
+        let buf = &mut [0; 8][..layout.width()];
+        for (step, &offset) in chain.offsets.iter().enumerate() {
+            proc.read_exact_at(buf, addr)
+                .map_err(|_| ResolveError::Unreadable { step, addr })?;
+            let value = layout.read(buf);
+            if value == 0 {
+                return Err(ResolveError::NullPointer { step, addr });
+            }
+            let result = value
+                .checked_add_signed(offset)
+                .ok_or(ResolveError::Overflow { step: Some(step) })?;
+            f(addr, value, offset, result);
+            addr = result;
+        }
+        Ok(addr)
+    }
+}
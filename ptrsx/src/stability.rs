@@ -0,0 +1,109 @@
+use core::cmp::Ordering;
+
+use super::{OfflineResolver, PointerChain};
+
+// 每多一层偏移扣除的分数
+const LENGTH_PENALTY: f64 = 0.01;
+// 最大偏移每增加一倍扣除的分数
+const OFFSET_PENALTY: f64 = 0.001;
+
+// 指针链在多次转储中的稳定性
+#[derive(Debug, Clone)]
+pub struct ChainScore {
+    pub chain: PointerChain,
+    // 解析到目标地址的转储数量
+    pub survived: usize,
+    // 转储总数
+    pub total: usize,
+    pub score: f64,
+}
+
+// 统计一组指针链在多次转储中的存活次数，每次添加一个转储，
+// 不需要同时加载全部指针文件
+pub struct StabilityScorer {
+    chains: Vec<PointerChain>,
+    survived: Vec<usize>,
+    total: usize,
+}
+
+impl StabilityScorer {
+    pub fn new(chains: Vec<PointerChain>) -> Self {
+        let survived = vec![0; chains.len()];
+        Self { chains, survived, total: 0 }
+    }
+
+    pub fn chains(&self) -> &[PointerChain] {
+        &self.chains
+    }
+
+    // 添加一个转储，addr 是该转储中的目标地址
+    pub fn add_dump(&mut self, resolver: &OfflineResolver, addr: usize) {
+        self.total += 1;
+        for (chain, survived) in self.chains.iter().zip(&mut self.survived) {
+            if resolver.resolve(chain).is_ok_and(|x| x == addr) {
+                *survived += 1;
+            }
+        }
+    }
+
+    // 存活次数多的指针链总是排在前面，存活次数相同时按分数从高到低排序，
+    // 都相同时保持原来的顺序 分数 = 存活比例 - 偏移数量 * 0.01 - log2(1 +
+    // 最大偏移的绝对值) * 0.001
+    pub fn ranked(self) -> Vec<ChainScore> {
+        let total = self.total;
+        let mut scores = self
+            .chains
+            .into_iter()
+            .zip(self.survived)
+            .map(|(chain, survived)| {
+                let score = score(&chain, survived, total);
+                ChainScore { chain, survived, total, score }
+            })
+            .collect::<Vec<_>>();
+        scores.sort_by(|a, b| {
+            b.survived
+                .cmp(&a.survived)
+                .then_with(|| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal))
+        });
+        scores
+    }
+}
+
+fn score(chain: &PointerChain, survived: usize, total: usize) -> f64 {
+    let ratio = match total {
+        0 => 0.0,
+        n => survived as f64 / n as f64,
+    };
+    let max_offset = chain.offsets.iter().map(|x| x.unsigned_abs()).max().unwrap_or(0);
+    let length = chain.offsets.len() as f64;
+    ratio - length * LENGTH_PENALTY - (1.0 + max_offset as f64).log2() * OFFSET_PENALTY
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain(offsets: &[isize]) -> PointerChain {
+        PointerChain {
+            module: Some("app[0]".into()),
+            offset: 0x10,
+            offsets: offsets.to_vec(),
+        }
+    }
+
+    #[test]
+    fn survival_ranks_before_penalty() {
+        let stable = chain(&[isize::MAX, 8, 8, 8, 8, 8, 8, 8]);
+        let flaky = chain(&[8]);
+        let short = chain(&[0x10, 8, 8, 8, 8, 8, 8, 8]);
+        let scorer = StabilityScorer {
+            chains: vec![flaky.clone(), stable.clone(), short.clone()],
+            survived: vec![7, 8, 8],
+            total: 8,
+        };
+        let ranked = scorer.ranked();
+        let chains = ranked.iter().map(|x| &x.chain).collect::<Vec<_>>();
+        assert_eq!(chains, [&short, &stable, &flaky]);
+        assert!(ranked[1].score < ranked[2].score);
+    }
+}
@@ -1,6 +1,7 @@
 use std::{collections::BTreeSet, path::PathBuf};
 
 use argh::{FromArgValue, FromArgs};
+use ptrsx::{ModuleBase, OffsetPattern};
 
 pub struct AddressList(pub Vec<usize>);
 
@@ -16,6 +17,16 @@ impl FromArgValue for AddressList {
     }
 }
 
+pub struct Address(pub usize);
+
+impl FromArgValue for Address {
+    fn from_arg_value(value: &str) -> Result<Self, String> {
+        usize::from_str_radix(value.trim_start_matches("0x"), 16)
+            .map(Self)
+            .map_err(|e| e.to_string())
+    }
+}
+
 pub struct Range(pub (usize, usize));
 
 impl FromArgValue for Range {
@@ -36,9 +47,12 @@ pub struct Commands {
 
 #[derive(FromArgs)]
 #[argh(subcommand)]
+#[allow(clippy::large_enum_variant)]
 pub enum CommandEnum {
     Scan(SubCommandScan),
     Diff(SubCommandDiff),
+    Validate(SubCommandValidate),
+    Stability(SubCommandStability),
 }
 
 #[derive(FromArgs)]
@@ -64,17 +78,111 @@ pub struct SubCommandScan {
     pub max: Option<usize>,
     #[argh(option, description = "last offset")]
     pub last: Option<isize>,
+    #[argh(option, description = "offset pattern from base to target, e.g. *.0x18.?.[0x10..0x40]")]
+    pub pattern: Option<OffsetPattern>,
+    #[argh(option, description = "every offset must be a multiple of this value")]
+    pub offset_align: Option<usize>,
+    #[argh(option, description = "only use this module as base, name or glob, can be repeated")]
+    pub include: Vec<String>,
+    #[argh(option, description = "never use this module as base, name or glob, can be repeated")]
+    pub exclude: Vec<String>,
+    #[argh(option, default = "false", description = "output shorter chains first. default false")]
+    pub shortest: bool,
     #[argh(option, description = "out dir")]
     pub dir: Option<PathBuf>,
+    #[argh(option, short = 't', description = "threads per target address. default cpus / targets")]
+    pub threads: Option<usize>,
+    #[argh(
+        option,
+        default = "false",
+        description = "scan all target addresses in one traversal. default false"
+    )]
+    pub shared: bool,
+    #[argh(
+        option,
+        description = "write all target addresses to one file, each line starts with the address"
+    )]
+    pub combined: Option<PathBuf>,
+    #[argh(
+        option,
+        description = "write chains that reach every target address through one object to this file, cannot be used \
+                       with combined"
+    )]
+    pub fields: Option<PathBuf>,
 }
 
 #[derive(FromArgs)]
-#[argh(subcommand, name = "diff", description = "Get the intersection of two .scandata files.")]
+#[argh(subcommand, name = "diff", description = "Get the intersection of .scandata files.")]
 pub struct SubCommandDiff {
+    #[argh(positional, description = "file names")]
+    pub files: Vec<PathBuf>,
     #[argh(option, description = "file1 name")]
-    pub f1: PathBuf,
+    pub f1: Option<PathBuf>,
     #[argh(option, description = "file2 name")]
-    pub f2: PathBuf,
+    pub f2: Option<PathBuf>,
+    #[argh(option, short = 'k', description = "keep chains present in at least k files. default all files")]
+    pub min: Option<usize>,
+    #[argh(option, default = "256", description = "memory limit in MiB. default 256")]
+    pub memory: usize,
+    #[argh(option, description = "out file name")]
+    pub out: Option<PathBuf>,
+}
+
+#[derive(FromArgs)]
+#[argh(
+    subcommand,
+    name = "validate",
+    description = "Keep chains of a .scandata file that still reach the target in another dump."
+)]
+pub struct SubCommandValidate {
+    #[argh(option, description = "binary file path of the other dump")]
+    pub bin: PathBuf,
+    #[argh(option, description = "info file path of the other dump")]
+    pub info: PathBuf,
+    #[argh(option, description = "pointer chain file")]
+    pub chains: PathBuf,
+    #[argh(option, short = 'a', description = "target address in the other dump, hex")]
+    pub addr: Address,
+    #[argh(
+        option,
+        default = "false",
+        description = "chains use module offsets relative to the load base. default false"
+    )]
+    pub load_base: bool,
+    #[argh(option, description = "out file name")]
+    pub out: Option<PathBuf>,
+}
+
+#[derive(FromArgs)]
+#[argh(
+    subcommand,
+    name = "stability",
+    description = "Rank chains of a .scandata file by how many other dumps they survive."
+)]
+pub struct SubCommandStability {
+    #[argh(option, description = "pointer chain file")]
+    pub chains: PathBuf,
+    #[argh(option, description = "binary file path of a dump, can be repeated")]
+    pub bin: Vec<PathBuf>,
+    #[argh(option, description = "info file path of a dump, in the same order as --bin")]
+    pub info: Vec<PathBuf>,
+    #[argh(option, short = 'a', description = "target address in a dump, hex, in the same order as --bin")]
+    pub addr: Vec<Address>,
+    #[argh(option, description = "only output chains that survive at least this many dumps")]
+    pub min: Option<usize>,
+    #[argh(
+        option,
+        default = "false",
+        description = "chains use module offsets relative to the load base. default false"
+    )]
+    pub load_base: bool,
     #[argh(option, description = "out file name")]
     pub out: Option<PathBuf>,
 }
+
+pub fn module_base(load_base: bool) -> ModuleBase {
+    match load_base {
+        true => ModuleBase::Load,
+        false => ModuleBase::Writable,
+    }
+}
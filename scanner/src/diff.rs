@@ -1,19 +1,32 @@
 use std::{
-    collections::HashSet,
-    fs::{self, OpenOptions},
-    io::{self, BufWriter, Write},
+    collections::{hash_map::DefaultHasher, HashMap},
+    env,
+    fs::{self, File, OpenOptions},
+    hash::{BuildHasher, Hash, Hasher, RandomState},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::PathBuf,
+    process,
 };
 
+use ptrsx::PointerChain;
+
 use super::{Error, SubCommandDiff};
 
+// 临时文件同时处于打开状态，数量受进程可打开的文件数限制，
+// 输入超过 memory * MAX_BUCKETS 时内存占用会超过 memory
+const MAX_BUCKETS: usize = 512;
+
 impl SubCommandDiff {
     pub fn init(self) -> Result<(), Error> {
-        let SubCommandDiff { f1, f2, out } = self;
-
-        let h1 = fs::read_to_string(f1)?;
-        let h2 = fs::read_to_string(f2)?;
-        let h1 = h1.lines().collect::<HashSet<_>>();
-        let h2 = h2.lines().collect::<HashSet<_>>();
+        let SubCommandDiff { files, f1, f2, min, memory, out } = self;
+        let files = f1.into_iter().chain(f2).chain(files).collect::<Vec<_>>();
+        if files.len() < 2 {
+            return Err("at least two files are required.".into());
+        }
+        let min = min.unwrap_or(files.len());
+        if min == 0 || min > files.len() {
+            return Err(format!("k must be between 1 and {}.", files.len()).into());
+        }
 
         let out: Box<dyn Write> = match out {
             Some(file) => Box::new(OpenOptions::new().append(true).create(true).open(file)?) as _,
@@ -21,6 +34,122 @@ impl SubCommandDiff {
         };
         let mut out = BufWriter::new(out);
 
-        Ok(h1.intersection(&h2).try_for_each(|s| writeln!(out, "{s}"))?)
+        // 按哈希把全部文件的行分到多个临时文件中，相同的指针链一定在同一个临时文件里，
+        // 每次只把一个临时文件读入内存，内存占用约为 输入总大小 / 临时文件数量
+        let total = files
+            .iter()
+            .map(|f| fs::metadata(f).map(|m| m.len()))
+            .sum::<io::Result<u64>>()?;
+        let limit = (memory.max(1) as u64) << 20;
+        let buckets = (total.div_ceil(limit).max(1) as usize).min(MAX_BUCKETS);
+
+        let dir = TempDir::new()?;
+        let mut writers = (0..buckets)
+            .map(|i| Ok(BufWriter::new(File::create(dir.bucket(i))?)))
+            .collect::<io::Result<Vec<_>>>()?;
+        let line_buf = &mut String::with_capacity(0x2000);
+        for (idx, file) in files.iter().enumerate() {
+            let mut reader = BufReader::with_capacity(0x80000, File::open(file)?);
+            loop {
+                line_buf.clear();
+                if reader.read_line(line_buf)? == 0 {
+                    break;
+                }
+                let Some(line) = normalize(line_buf) else {
+                    continue;
+                };
+                let mut hasher = DefaultHasher::new();
+                line.hash(&mut hasher);
+                let bucket = (hasher.finish() % buckets as u64) as usize;
+                writeln!(writers[bucket], "{idx} {line}")?;
+            }
+        }
+        for mut writer in writers {
+            writer.flush()?;
+        }
+
+        // 临时文件中的行按输入文件的顺序写入，
+        // 记录最后出现的文件即可去除同一文件中的重复行
+        for i in 0..buckets {
+            let contents = fs::read_to_string(dir.bucket(i))?;
+            let mut counts = HashMap::<&str, (usize, usize)>::new();
+            for line in contents.lines() {
+                let Some((idx, chain)) = line.split_once(' ') else {
+                    continue;
+                };
+                let idx = idx.parse::<usize>()?;
+                let (count, last) = counts.entry(chain).or_insert((0, usize::MAX));
+                if *last != idx {
+                    *count += 1;
+                    *last = idx;
+                }
+            }
+            // HashMap 的遍历顺序每次运行都不同，排序后输出
+            let mut chains = counts
+                .into_iter()
+                .filter(|(_, (count, _))| *count >= min)
+                .map(|(chain, _)| chain)
+                .collect::<Vec<_>>();
+            chains.sort_unstable();
+            chains.into_iter().try_for_each(|chain| writeln!(out, "{chain}"))?;
+            fs::remove_file(dir.bucket(i))?;
+        }
+
+        Ok(out.flush()?)
+    }
+}
+
+// 统一十六进制和十进制等不同写法，无法解析的行按原样比较
+fn normalize(line: &str) -> Option<String> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+    match line.parse::<PointerChain>() {
+        Ok(chain) => Some(chain.to_string()),
+        Err(_) => Some(line.to_string()),
+    }
+}
+
+// 退出时删除的临时目录，目录名带随机后缀，不会使用已经存在的目录
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new() -> io::Result<Self> {
+        let state = RandomState::new();
+        let mut attempt = 0_u32;
+        loop {
+            let suffix = state.hash_one(attempt);
+            let path = env::temp_dir().join(format!("ptrsx-diff-{}-{suffix:016x}", process::id()));
+            match fs::create_dir(&path) {
+                Ok(()) => return Ok(Self(path)),
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists && attempt < 16 => attempt += 1,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    fn bucket(&self, i: usize) -> PathBuf {
+        self.0.join(format!("{i}.tmp"))
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::normalize;
+
+    #[test]
+    fn normalize_chain() {
+        let hex = normalize("libc.so.6[0]+0x10.0x8.-0x10\n");
+        assert_eq!(hex.as_deref(), Some("libc.so.6[0]+16.8.-16"));
+        assert_eq!(normalize("libc.so.6[0]+16.8.-16"), hex);
+        assert_eq!(normalize("  not a chain "), Some("not a chain".into()));
+        assert_eq!(normalize(" \n"), None);
     }
 }
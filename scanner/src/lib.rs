@@ -2,7 +2,9 @@ mod cmd;
 mod diff;
 mod error;
 mod scan;
+mod stability;
 mod utils;
+mod validate;
 
 pub use cmd::*;
 pub use error::*;
@@ -1,12 +1,16 @@
-use std::{fs::File, thread};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    thread,
+};
 
-use ptrsx::{PtrsxScanner, UserParam};
+use ptrsx::{PointerChain, Progress, PtrsxScanner, UserParam};
 use rayon::{
     iter::{IntoParallelIterator, ParallelIterator},
     ThreadPool, ThreadPoolBuilder,
 };
 
-use super::{AddressList, Error, Range, Spinner, SubCommandScan};
+use super::{AddressList, Error, ProgressBar, Range, Spinner, SubCommandScan};
 
 impl SubCommandScan {
     pub fn init(self) -> Result<(), Error> {
@@ -21,34 +25,115 @@ impl SubCommandScan {
             use_cycle,
             max,
             last,
+            pattern,
+            offset_align,
+            include,
+            exclude,
+            shortest,
             dir,
+            threads,
+            shared,
+            combined,
+            fields,
         } = self;
 
         if node.is_some_and(|n| depth <= n) {
             return Err("depth must be greater than node.".into());
         }
+        if fields.is_some() && combined.is_some() {
+            return Err("fields cannot be used with combined.".into());
+        }
 
         let mut spinner = Spinner::start("start loading cache...");
         let mut ptrsx = PtrsxScanner::default();
         let info = File::open(info)?;
         ptrsx.load_modules_info(info)?;
-        let bin = File::open(bin)?;
-        ptrsx.load_pointer_map(bin)?;
+        ptrsx.load_pointer_map_file(bin)?;
         spinner.stop("cache load is finished.");
 
-        let mut spinner = Spinner::start("start scanning pointer chain...");
+        let progress = Progress::new();
+        let mut bar = ProgressBar::start("scanning pointer chain...", progress.clone());
 
         let dir = dir.unwrap_or_default();
 
+        // 所有目标地址共享同一次遍历
+        if shared || combined.is_some() || fields.is_some() {
+            let threads = Some(threads.unwrap_or(thread::available_parallelism()?.get()));
+            let param = UserParam {
+                depth,
+                addr: 0,
+                range,
+                use_module,
+                use_cycle,
+                node,
+                max,
+                last,
+                pattern,
+                offset_align,
+                include,
+                exclude,
+                shortest,
+                threads,
+                progress: Some(progress.clone()),
+            };
+            match (fields, combined) {
+                (Some(path), _) => ptrsx.pointer_chain_scanner_fields(param, &list, path)?,
+                (None, Some(path)) => ptrsx.pointer_chain_scanner_multi(param, &list, path)?,
+                (None, None) => {
+                    let mut writers = list
+                        .iter()
+                        .map(|addr| {
+                            let path = dir.join(format!("{addr:x}")).with_extension("scandata");
+                            let file = File::options().append(true).create_new(true).open(path)?;
+                            Ok(BufWriter::new(file))
+                        })
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    let mut f = |i: usize, chain: &PointerChain| writeln!(writers[i], "{chain}");
+                    ptrsx.try_pointer_chain_scanner_multi(param, &list, (), &mut f)?;
+                    for mut writer in writers {
+                        writer.flush()?;
+                    }
+                    if progress.is_cancelled() {
+                        return Err(ptrsx::Error::Cancelled.into());
+                    }
+                }
+            }
+            bar.stop("pointer chain scan is finished.");
+            return Ok(());
+        }
+
+        let threads = match threads {
+            Some(n) => n,
+            None => (thread::available_parallelism()?.get() / list.len().max(1)).max(1),
+        };
+        let threads = Some(threads);
+
         rayon_create_pool(list.len())?.install(|| {
             list.into_par_iter().try_for_each(|addr| {
                 let path = dir.join(format!("{addr:x}")).with_extension("scandata");
-                let param = UserParam { depth, addr, range, use_module, use_cycle, node, max, last };
+                let progress = Some(progress.clone());
+                let param = UserParam {
+                    depth,
+                    addr,
+                    range,
+                    use_module,
+                    use_cycle,
+                    node,
+                    max,
+                    last,
+                    pattern: pattern.clone(),
+                    offset_align,
+                    include: include.clone(),
+                    exclude: exclude.clone(),
+                    shortest,
+                    threads,
+                    progress,
+                };
                 ptrsx.pointer_chain_scanner(param, path)
             })
         })?;
 
-        spinner.stop("pointer chain scan is finished.");
+        bar.stop("pointer chain scan is finished.");
 
         Ok(())
     }
@@ -0,0 +1,51 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+};
+
+use ptrsx::{ChainScore, PointerChain, PtrsxScanner, StabilityScorer};
+
+use super::{module_base, Address, Error, Spinner, SubCommandStability};
+
+impl SubCommandStability {
+    pub fn init(self) -> Result<(), Error> {
+        let SubCommandStability { chains, bin, info, addr, min, load_base, out } = self;
+        if bin.len() != info.len() || bin.len() != addr.len() {
+            return Err("--bin, --info and --addr must be given the same number of times.".into());
+        }
+
+        // 无法解析的行视为无效
+        let contents = fs::read_to_string(chains)?;
+        let chains = contents
+            .lines()
+            .filter_map(|line| line.trim().parse::<PointerChain>().ok())
+            .collect();
+        let mut scorer = StabilityScorer::new(chains);
+
+        for (bin, (info, Address(addr))) in bin.into_iter().zip(info.into_iter().zip(addr)) {
+            let mut spinner = Spinner::start(format!("checking {}...", bin.display()));
+            let mut ptrsx = PtrsxScanner::default();
+            ptrsx.load_modules_info(File::open(info)?)?;
+            ptrsx.load_pointer_map_file(&bin)?;
+            // 所有指针文件都要与指针链使用相同的模块基址
+            ptrsx.check_module_base(module_base(load_base))?;
+            scorer.add_dump(&ptrsx.offline_resolver(), addr);
+            spinner.stop(format!("{} is checked.", bin.display()));
+        }
+
+        let out: Box<dyn Write> = match out {
+            Some(file) => Box::new(OpenOptions::new().append(true).create(true).open(file)?) as _,
+            None => Box::new(io::stdout()) as _,
+        };
+        let mut out = BufWriter::new(out);
+        let min = min.unwrap_or(0);
+        for ChainScore { chain, survived, total, score } in scorer.ranked() {
+            if survived >= min {
+                writeln!(out, "{score:.4} {survived}/{total} {chain}")?;
+            }
+        }
+        out.flush()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufReader, BufWriter, Write},
+};
+
+use ptrsx::PtrsxScanner;
+
+use super::{module_base, Address, Error, Spinner, SubCommandValidate};
+
+impl SubCommandValidate {
+    pub fn init(self) -> Result<(), Error> {
+        let SubCommandValidate { bin, info, chains, addr: Address(addr), load_base, out } = self;
+
+        let mut spinner = Spinner::start("start loading cache...");
+        let mut ptrsx = PtrsxScanner::default();
+        ptrsx.load_modules_info(File::open(info)?)?;
+        ptrsx.load_pointer_map_file(bin)?;
+        ptrsx.check_module_base(module_base(load_base))?;
+        spinner.stop("cache load is finished.");
+
+        let reader = BufReader::with_capacity(0x80000, File::open(chains)?);
+        let out: Box<dyn Write> = match out {
+            Some(file) => Box::new(OpenOptions::new().append(true).create(true).open(file)?) as _,
+            None => Box::new(io::stdout()) as _,
+        };
+        let writer = BufWriter::new(out);
+
+        let (total, valid) = ptrsx.validate_chains(reader, addr, writer)?;
+        eprintln!("\x1B[34m[*]\x1B[0m {valid} of {total} pointer chains are valid.");
+
+        Ok(())
+    }
+}
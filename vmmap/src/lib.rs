@@ -8,7 +8,7 @@ pub mod windows;
 
 pub use self::error::Error;
 #[cfg(any(target_os = "linux", target_os = "android"))]
-pub use self::linux::{Mapping, Process};
+pub use self::linux::{CoreDump, Mapping, Minidump, Process, RawImage, RemoteProcess};
 #[cfg(target_os = "macos")]
 pub use self::macos::{Mapping, Process};
 #[cfg(target_os = "windows")]
@@ -46,4 +46,9 @@ pub trait ProcessInfo {
     fn pid(&self) -> Pid;
     fn app_path(&self) -> &std::path::Path;
     fn get_maps(&self) -> impl Iterator<Item = Result<Mapping>>;
+    // 名称为 name 的内存区域能否作为基址模块，None 表示由调用者读取本机的文件判断，
+    // 文件不在本机的来源需要自己判断
+    fn is_module(&self, _name: &str) -> Option<bool> {
+        None
+    }
 }
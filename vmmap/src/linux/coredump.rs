@@ -0,0 +1,325 @@
+use std::{
+    fs::File,
+    io,
+    os::unix::prelude::FileExt,
+    path::{Path, PathBuf},
+};
+
+use super::{is_file_module, Error, Mapping, Pid, ProcessInfo, Result, VirtualMemoryRead};
+
+const PT_LOAD: u32 = 1;
+const PT_NOTE: u32 = 4;
+const PF_X: u32 = 1;
+const PF_W: u32 = 2;
+const PF_R: u32 = 4;
+const NT_PRPSINFO: u32 = 3;
+const NT_FILE: u32 = 0x4649_4c45;
+const PN_XNUM: u16 = 0xffff;
+
+// ELF core 文件，例如 gcore 或内核生成的 core dump，
+// 每个 PT_LOAD 段对应一个内存区域，区域名称来自 NT_FILE 注释
+// [heap] [stack] 等没有文件的区域在 core 文件中没有名称
+pub struct CoreDump {
+    pub pid: Pid,
+    pub pathname: PathBuf,
+    pub file: File,
+    segments: Vec<Segment>,
+    files: Vec<FileEntry>,
+}
+
+// PT_LOAD 段，filesz 小于 memsz 时超出的部分没有写入 core 文件
+struct Segment {
+    vaddr: usize,
+    memsz: usize,
+    offset: u64,
+    filesz: usize,
+    flags: u32,
+}
+
+// NT_FILE 中的一个文件映射，offset 是文件内的字节偏移
+struct FileEntry {
+    start: usize,
+    end: usize,
+    offset: usize,
+    name: String,
+}
+
+impl VirtualMemoryRead for CoreDump {
+    fn read_at(&self, buf: &mut [u8], offset: usize) -> Result<usize> {
+        self._read_at(buf, offset).map_err(Error::ReadMemory)
+    }
+
+    fn read_exact_at(&self, buf: &mut [u8], offset: usize) -> Result<()> {
+        let mut done = 0;
+        while done < buf.len() {
+            done += self.read_at(&mut buf[done..], offset + done)?;
+        }
+        Ok(())
+    }
+}
+
+impl ProcessInfo for CoreDump {
+    fn pid(&self) -> Pid {
+        self.pid
+    }
+
+    fn app_path(&self) -> &Path {
+        &self.pathname
+    }
+
+    fn get_maps(&self) -> impl Iterator<Item = Result<Mapping>> {
+        self.segments.iter().map(|s| {
+            let file = self.files.iter().find(|f| f.start <= s.vaddr && s.vaddr < f.end);
+            let flag = |bit, c| if s.flags & bit != 0 { c } else { '-' };
+            Ok(Mapping {
+                start: s.vaddr,
+                end: s.vaddr + s.memsz,
+                flags: [flag(PF_R, 'r'), flag(PF_W, 'w'), flag(PF_X, 'x'), 'p']
+                    .iter()
+                    .collect(),
+                offset: file.map_or(0, |f| f.offset + (s.vaddr - f.start)),
+                dev: String::from("00:00"),
+                inode: 0,
+                name: file.map(|f| f.name.clone()),
+            })
+        })
+    }
+
+    // core 文件可能来自其他机器，不读取本机的文件
+    fn is_module(&self, name: &str) -> Option<bool> {
+        Some(is_file_module(name))
+    }
+}
+
+impl CoreDump {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::_open(path.as_ref()).map_err(Error::OpenProcess)
+    }
+
+    fn _open(path: &Path) -> Result<Self, io::Error> {
+        let file = File::open(path)?;
+        let mut ehdr = [0; 64];
+        file.read_exact_at(&mut ehdr[..52], 0)?;
+        if ehdr[..4] != [0x7f, b'E', b'L', b'F'] {
+            return Err(invalid("not an ELF file"));
+        }
+        let elf = match (ehdr[4], ehdr[5]) {
+            (1, 1) => Elf { is64: false, le: true },
+            (1, 2) => Elf { is64: false, le: false },
+            (2, 1) => Elf { is64: true, le: true },
+            (2, 2) => Elf { is64: true, le: false },
+            _ => return Err(invalid("unsupported ELF class or byte order")),
+        };
+        if elf.is64 {
+            file.read_exact_at(&mut ehdr[52..], 52)?;
+        }
+        // ET_CORE
+        if elf.u16(&ehdr, 0x10) != 4 {
+            return Err(invalid("not an ELF core file"));
+        }
+
+        let (phoff, phentsize, phnum) = match elf.is64 {
+            true => (elf.u64(&ehdr, 0x20), elf.u16(&ehdr, 0x36), elf.u16(&ehdr, 0x38)),
+            false => (elf.u32(&ehdr, 0x1c) as u64, elf.u16(&ehdr, 0x2a), elf.u16(&ehdr, 0x2c)),
+        };
+        let (phentsize, phnum) = (phentsize as usize, elf.phnum(&file, &ehdr, phnum)?);
+        if phentsize < if elf.is64 { 0x38 } else { 0x20 } {
+            return Err(invalid("invalid program header size"));
+        }
+        let len = file.metadata()?.len();
+        let size = phentsize
+            .checked_mul(phnum)
+            .filter(|&n| n as u64 <= len)
+            .ok_or_else(|| invalid("invalid program header count"))?;
+        let mut phdrs = vec![0; size];
+        file.read_exact_at(&mut phdrs, phoff)?;
+
+        let mut segments = Vec::new();
+        let mut notes = Vec::new();
+        for ph in phdrs.chunks_exact(phentsize) {
+            let (p_type, flags, offset, vaddr, filesz, memsz) = match elf.is64 {
+                true => (
+                    elf.u32(ph, 0),
+                    elf.u32(ph, 4),
+                    elf.u64(ph, 0x08),
+                    elf.u64(ph, 0x10),
+                    elf.u64(ph, 0x20),
+                    elf.u64(ph, 0x28),
+                ),
+                false => (
+                    elf.u32(ph, 0),
+                    elf.u32(ph, 0x18),
+                    elf.u32(ph, 0x04) as u64,
+                    elf.u32(ph, 0x08) as u64,
+                    elf.u32(ph, 0x10) as u64,
+                    elf.u32(ph, 0x14) as u64,
+                ),
+            };
+            match p_type {
+                PT_LOAD if memsz != 0 => segments.push(Segment {
+                    vaddr: to_usize(vaddr)?,
+                    memsz: to_usize(memsz)?,
+                    offset,
+                    filesz: to_usize(filesz.min(memsz))?,
+                    flags,
+                }),
+                PT_NOTE => {
+                    if offset.checked_add(filesz).is_none_or(|end| end > len) {
+                        return Err(invalid("note segment is out of file range"));
+                    }
+                    let mut buf = vec![0; to_usize(filesz)?];
+                    file.read_exact_at(&mut buf, offset)?;
+                    notes.push(buf);
+                }
+                _ => {}
+            }
+        }
+        segments.sort_unstable_by_key(|s| s.vaddr);
+
+        let mut pid = 0;
+        let mut files = Vec::new();
+        for (n_type, desc) in notes.iter().flat_map(|buf| elf.notes(buf)) {
+            match n_type {
+                NT_PRPSINFO => pid = elf.prpsinfo_pid(desc).unwrap_or(pid),
+                NT_FILE => files = elf.file_entries(desc).ok_or_else(|| invalid("invalid NT_FILE note"))?,
+                _ => {}
+            }
+        }
+        // 第一个文件映射通常是可执行文件
+        let pathname = files.first().map_or_else(PathBuf::new, |f| PathBuf::from(&f.name));
+
+        Ok(Self { pid, pathname, file, segments, files })
+    }
+
+    // 只读取 addr 所在的段，跨越多个段时由调用者继续读取
+    fn _read_at(&self, buf: &mut [u8], addr: usize) -> Result<usize, io::Error> {
+        let idx = self.segments.partition_point(|s| s.vaddr <= addr);
+        let segment = idx
+            .checked_sub(1)
+            .map(|i| &self.segments[i])
+            .filter(|s| addr - s.vaddr < s.filesz)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "address is not in the core file"))?;
+        let start = addr - segment.vaddr;
+        let size = buf.len().min(segment.filesz - start);
+        self.file
+            .read_exact_at(&mut buf[..size], segment.offset + start as u64)?;
+        Ok(size)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Elf {
+    is64: bool,
+    le: bool,
+}
+
+impl Elf {
+    // 程序头数量为 PN_XNUM 时，实际数量在第 0 个节头的 sh_info 中
+    fn phnum(&self, file: &File, ehdr: &[u8], phnum: u16) -> Result<usize, io::Error> {
+        if phnum != PN_XNUM {
+            return Ok(phnum as usize);
+        }
+        let shoff = match self.is64 {
+            true => self.u64(ehdr, 0x28),
+            false => self.u32(ehdr, 0x20) as u64,
+        };
+        if shoff == 0 {
+            return Err(invalid("PN_XNUM without section header"));
+        }
+        let mut shdr = [0; 4];
+        let pos = if self.is64 { 0x2c } else { 0x1c };
+        file.read_exact_at(&mut shdr, shoff + pos)?;
+        to_usize(self.u32(&shdr, 0) as u64)
+    }
+
+    fn uint(&self, buf: &[u8], pos: usize, size: usize) -> u64 {
+        let mut n = [0; 8];
+        let bytes = buf.get(pos..pos + size).unwrap_or(&[0; 8][..size]);
+        match self.le {
+            true => {
+                n[..size].copy_from_slice(bytes);
+                u64::from_le_bytes(n)
+            }
+            false => {
+                n[8 - size..].copy_from_slice(bytes);
+                u64::from_be_bytes(n)
+            }
+        }
+    }
+
+    fn u16(&self, buf: &[u8], pos: usize) -> u16 {
+        self.uint(buf, pos, 2) as u16
+    }
+
+    fn u32(&self, buf: &[u8], pos: usize) -> u32 {
+        self.uint(buf, pos, 4) as u32
+    }
+
+    fn u64(&self, buf: &[u8], pos: usize) -> u64 {
+        self.uint(buf, pos, 8)
+    }
+
+    // 指针宽度的整数
+    fn word(&self, buf: &[u8], pos: usize) -> u64 {
+        self.uint(buf, pos, self.word_size())
+    }
+
+    fn word_size(&self) -> usize {
+        if self.is64 {
+            8
+        } else {
+            4
+        }
+    }
+
+    // 注释段中的全部 (类型, 内容)，名称和内容都按 4 字节对齐
+    fn notes<'a>(&self, buf: &'a [u8]) -> impl Iterator<Item = (u32, &'a [u8])> {
+        let elf = *self;
+        let mut pos = 0;
+        core::iter::from_fn(move || {
+            let namesz = elf.u32(buf, pos) as usize;
+            let descsz = elf.u32(buf, pos + 4) as usize;
+            let n_type = elf.u32(buf, pos + 8);
+            let desc = pos + 12 + namesz.next_multiple_of(4);
+            let data = buf.get(desc..desc.checked_add(descsz)?)?;
+            pos = desc + descsz.next_multiple_of(4);
+            Some((n_type, data))
+        })
+    }
+
+    // elf_prpsinfo 中 pr_pid 的位置与指针宽度有关
+    fn prpsinfo_pid(&self, desc: &[u8]) -> Option<Pid> {
+        let pos = if self.is64 { 24 } else { 12 };
+        desc.get(pos..pos + 4)?;
+        Some(self.u32(desc, pos) as Pid)
+    }
+
+    // count page_size [start end file_ofs]*count 之后是 count 个以 0 结尾的文件名
+    fn file_entries(&self, desc: &[u8]) -> Option<Vec<FileEntry>> {
+        let ws = self.word_size();
+        let count = to_usize(self.word(desc, 0)).ok()?;
+        let page_size = to_usize(self.word(desc, ws)).ok()?;
+        let names_pos = count.checked_mul(ws * 3)?.checked_add(ws * 2)?;
+        let mut names = desc.get(names_pos..)?.split(|&b| b == 0);
+        (0..count)
+            .map(|i| {
+                let pos = ws * 2 + i * ws * 3;
+                let start = to_usize(self.word(desc, pos)).ok()?;
+                let end = to_usize(self.word(desc, pos + ws)).ok()?;
+                let offset = to_usize(self.word(desc, pos + ws * 2)).ok()?.checked_mul(page_size)?;
+                let name = String::from_utf8_lossy(names.next()?).into_owned();
+                Some(FileEntry { start, end, offset, name })
+            })
+            .collect()
+    }
+}
+
+#[inline]
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[inline]
+fn to_usize(n: u64) -> Result<usize, io::Error> {
+    usize::try_from(n).map_err(|_| invalid("value is too large"))
+}
@@ -0,0 +1,280 @@
+use std::{
+    fs::File,
+    io,
+    os::unix::prelude::FileExt,
+    path::{Path, PathBuf},
+};
+
+use super::{is_file_module, Error, Mapping, Pid, ProcessInfo, Result, VirtualMemoryRead};
+
+const SIGNATURE: u32 = 0x504d_444d; // MDMP
+const MODULE_LIST_STREAM: u32 = 4;
+const MEMORY_LIST_STREAM: u32 = 5;
+const MEMORY64_LIST_STREAM: u32 = 9;
+const MISC_INFO_STREAM: u32 = 15;
+const MEMORY_INFO_LIST_STREAM: u32 = 16;
+const MODULE_SIZE: usize = 108;
+const MISC1_PROCESS_ID: u32 = 1;
+
+const PAGE_NOACCESS: u32 = 0x01;
+const PAGE_READONLY: u32 = 0x02;
+const PAGE_READWRITE: u32 = 0x04;
+const PAGE_WRITECOPY: u32 = 0x08;
+const PAGE_EXECUTE: u32 = 0x10;
+const PAGE_EXECUTE_READ: u32 = 0x20;
+const PAGE_EXECUTE_READWRITE: u32 = 0x40;
+const PAGE_EXECUTE_WRITECOPY: u32 = 0x80;
+const PAGE_GUARD: u32 = 0x100;
+
+// Windows 小型转储文件 (.dmp)
+// 内存区域来自 MemoryListStream 和 Memory64ListStream
+// 权限来自 MemoryInfoListStream，没有权限信息时视为可读写
+// 位于模块范围内的区域使用 ModuleListStream 中的模块路径作为名称
+pub struct Minidump {
+    pub pid: Pid,
+    pub pathname: PathBuf,
+    pub file: File,
+    ranges: Vec<MemoryRange>,
+    infos: Vec<MemoryInfo>,
+    modules: Vec<Module>,
+}
+
+struct MemoryRange {
+    start: usize,
+    size: usize,
+    rva: u64,
+}
+
+struct MemoryInfo {
+    start: usize,
+    size: usize,
+    protect: u32,
+}
+
+struct Module {
+    start: usize,
+    size: usize,
+    name: String,
+}
+
+impl VirtualMemoryRead for Minidump {
+    fn read_at(&self, buf: &mut [u8], offset: usize) -> Result<usize> {
+        self._read_at(buf, offset).map_err(Error::ReadMemory)
+    }
+
+    fn read_exact_at(&self, buf: &mut [u8], offset: usize) -> Result<()> {
+        let mut done = 0;
+        while done < buf.len() {
+            done += self.read_at(&mut buf[done..], offset + done)?;
+        }
+        Ok(())
+    }
+}
+
+impl ProcessInfo for Minidump {
+    fn pid(&self) -> Pid {
+        self.pid
+    }
+
+    fn app_path(&self) -> &Path {
+        &self.pathname
+    }
+
+    fn get_maps(&self) -> impl Iterator<Item = Result<Mapping>> {
+        self.ranges.iter().map(|r| {
+            let protect = find(&self.infos, r.start, |x| (x.start, x.size)).map_or(PAGE_READWRITE, |x| x.protect);
+            let name = find(&self.modules, r.start, |x| (x.start, x.size)).map(|x| x.name.clone());
+            Ok(Mapping {
+                start: r.start,
+                end: r.start + r.size,
+                flags: flags(protect),
+                offset: 0,
+                dev: String::from("00:00"),
+                inode: 0,
+                name,
+            })
+        })
+    }
+
+    // 模块文件在转储的机器上，Windows 路径与 Windows 上一样排除系统目录
+    fn is_module(&self, name: &str) -> Option<bool> {
+        let windows = name.as_bytes().get(1..3).is_some_and(|s| s == b":\\") || name.starts_with('\\');
+        match windows {
+            true => Some(!name.contains("\\Windows\\")),
+            false => Some(is_file_module(name)),
+        }
+    }
+}
+
+impl Minidump {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::_open(path.as_ref()).map_err(Error::OpenProcess)
+    }
+
+    fn _open(path: &Path) -> Result<Self, io::Error> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        let header = read_vec(&file, len, 0, 32)?;
+        if u32_at(&header, 0) != SIGNATURE {
+            return Err(invalid("not a minidump file"));
+        }
+        let count = (u32_at(&header, 8) as usize).checked_mul(12).ok_or_else(truncated)?;
+        let directory = read_vec(&file, len, u32_at(&header, 12) as u64, count)?;
+
+        let mut pid = 0;
+        let mut ranges = Vec::new();
+        let mut infos = Vec::new();
+        let mut modules = Vec::new();
+        for entry in directory.chunks_exact(12) {
+            let (stream_type, size, rva) = (u32_at(entry, 0), u32_at(entry, 4) as usize, u32_at(entry, 8) as u64);
+            match stream_type {
+                MODULE_LIST_STREAM => {
+                    let data = read_vec(&file, len, rva, size)?;
+                    let count = u32_at(&data, 0) as usize;
+                    for m in data
+                        .get(4..4 + count * MODULE_SIZE)
+                        .ok_or_else(truncated)?
+                        .chunks_exact(MODULE_SIZE)
+                    {
+                        let name = read_string(&file, len, u32_at(m, 20) as u64)?;
+                        modules.push(Module { start: to_usize(u64_at(m, 0))?, size: u32_at(m, 8) as usize, name });
+                    }
+                }
+                MEMORY_LIST_STREAM => {
+                    let data = read_vec(&file, len, rva, size)?;
+                    let count = u32_at(&data, 0) as usize;
+                    for d in data.get(4..4 + count * 16).ok_or_else(truncated)?.chunks_exact(16) {
+                        let (start, size, rva) = (u64_at(d, 0), u32_at(d, 8) as usize, u32_at(d, 12) as u64);
+                        ranges.push(MemoryRange { start: to_usize(start)?, size, rva });
+                    }
+                }
+                MEMORY64_LIST_STREAM => {
+                    let data = read_vec(&file, len, rva, size)?;
+                    let count = to_usize(u64_at(&data, 0))?;
+                    let mut rva = u64_at(&data, 8);
+                    let descriptors = count.checked_mul(16).and_then(|n| data.get(16..n.checked_add(16)?));
+                    for d in descriptors.ok_or_else(truncated)?.chunks_exact(16) {
+                        let (start, size) = (u64_at(d, 0), u64_at(d, 8));
+                        ranges.push(MemoryRange { start: to_usize(start)?, size: to_usize(size)?, rva });
+                        rva = rva.checked_add(size).ok_or_else(truncated)?;
+                    }
+                }
+                MEMORY_INFO_LIST_STREAM => {
+                    let data = read_vec(&file, len, rva, size)?;
+                    let (header_size, entry_size) = (u32_at(&data, 0) as usize, u32_at(&data, 4) as usize);
+                    let count = to_usize(u64_at(&data, 8))?;
+                    if entry_size < 48 {
+                        return Err(invalid("invalid memory info entry size"));
+                    }
+                    let entries = count
+                        .checked_mul(entry_size)
+                        .and_then(|n| data.get(header_size..header_size.checked_add(n)?));
+                    for e in entries.ok_or_else(truncated)?.chunks_exact(entry_size) {
+                        let (start, size, protect) = (u64_at(e, 0), u64_at(e, 24), u32_at(e, 36));
+                        infos.push(MemoryInfo { start: to_usize(start)?, size: to_usize(size)?, protect });
+                    }
+                }
+                MISC_INFO_STREAM => {
+                    let data = read_vec(&file, len, rva, size)?;
+                    if u32_at(&data, 4) & MISC1_PROCESS_ID != 0 {
+                        pid = u32_at(&data, 8) as Pid;
+                    }
+                }
+                _ => {}
+            }
+        }
+        ranges.retain(|r| r.size != 0);
+        if ranges.iter().any(|r| r.start.checked_add(r.size).is_none()) {
+            return Err(invalid("memory range overflows the address space"));
+        }
+        ranges.sort_unstable_by_key(|r| r.start);
+        infos.sort_unstable_by_key(|x| x.start);
+        // 第一个模块是主程序
+        let pathname = modules.first().map_or_else(PathBuf::new, |m| PathBuf::from(&m.name));
+        modules.sort_unstable_by_key(|m| m.start);
+
+        Ok(Self { pid, pathname, file, ranges, infos, modules })
+    }
+
+    // 只读取 addr 所在的区域，跨越多个区域时由调用者继续读取
+    fn _read_at(&self, buf: &mut [u8], addr: usize) -> Result<usize, io::Error> {
+        let range = find(&self.ranges, addr, |r| (r.start, r.size))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "address is not in the minidump"))?;
+        let start = addr - range.start;
+        let size = buf.len().min(range.size - start);
+        self.file.read_exact_at(&mut buf[..size], range.rva + start as u64)?;
+        Ok(size)
+    }
+}
+
+// 在按起始地址排序的区域中查找包含 addr 的区域
+fn find<T>(items: &[T], addr: usize, range: impl Fn(&T) -> (usize, usize)) -> Option<&T> {
+    let idx = items.partition_point(|x| range(x).0 <= addr).checked_sub(1)?;
+    let (start, size) = range(&items[idx]);
+    (addr - start < size).then_some(&items[idx])
+}
+
+// 与 Windows 上 Mapping 的权限判断一致
+fn flags(protect: u32) -> String {
+    let read = PAGE_EXECUTE_READ
+        | PAGE_EXECUTE_READWRITE
+        | PAGE_EXECUTE_WRITECOPY
+        | PAGE_READONLY
+        | PAGE_READWRITE
+        | PAGE_WRITECOPY;
+    let write = PAGE_EXECUTE_READWRITE | PAGE_READWRITE;
+    let exec = PAGE_EXECUTE | PAGE_EXECUTE_READ | PAGE_EXECUTE_READWRITE | PAGE_EXECUTE_WRITECOPY;
+    let usable = protect & (PAGE_NOACCESS | PAGE_GUARD) == 0;
+    let flag = |mask, c| if usable && protect & mask != 0 { c } else { '-' };
+    [flag(read, 'r'), flag(write, 'w'), flag(exec, 'x'), 'p']
+        .iter()
+        .collect()
+}
+
+// MINIDUMP_STRING，长度是字节数，内容是 UTF-16LE
+fn read_string(file: &File, len: u64, rva: u64) -> Result<String, io::Error> {
+    let size = u32_at(&read_vec(file, len, rva, 4)?, 0) as usize;
+    let bytes = read_vec(file, len, rva + 4, size)?;
+    let units = bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect::<Vec<_>>();
+    Ok(String::from_utf16_lossy(&units))
+}
+
+// len 是文件长度，先检查范围，避免按损坏的长度字段分配过大的内存
+fn read_vec(file: &File, len: u64, offset: u64, size: usize) -> Result<Vec<u8>, io::Error> {
+    if offset.checked_add(size as u64).is_none_or(|end| end > len) {
+        return Err(truncated());
+    }
+    let mut buf = vec![0; size];
+    file.read_exact_at(&mut buf, offset)?;
+    Ok(buf)
+}
+
+#[inline]
+fn u32_at(buf: &[u8], pos: usize) -> u32 {
+    buf.get(pos..pos + 4)
+        .map_or(0, |b| u32::from_le_bytes(b.try_into().unwrap()))
+}
+
+#[inline]
+fn u64_at(buf: &[u8], pos: usize) -> u64 {
+    buf.get(pos..pos + 8)
+        .map_or(0, |b| u64::from_le_bytes(b.try_into().unwrap()))
+}
+
+#[inline]
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[inline]
+fn truncated() -> io::Error {
+    invalid("minidump stream is truncated")
+}
+
+#[inline]
+fn to_usize(n: u64) -> Result<usize, io::Error> {
+    usize::try_from(n).map_err(|_| invalid("value is too large"))
+}
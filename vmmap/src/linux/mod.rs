@@ -1,7 +1,15 @@
+mod coredump;
+mod minidump;
 mod proc;
+mod raw_image;
+pub mod remote;
 pub mod utils;
 
+pub use coredump::CoreDump;
+pub use minidump::Minidump;
 pub use proc::{Mapping, Process};
+pub use raw_image::{GuestRegion, RawImage};
+pub use remote::RemoteProcess;
 
 use super::{Error, Pid, ProcessInfo, Result, VirtualMemoryRead, VirtualMemoryWrite, VirtualQuery};
 
@@ -10,3 +18,11 @@ pub trait VirtualQueryExt {
     fn dev(&self) -> &str;
     fn inode(&self) -> usize;
 }
+
+// 文件不在本机时无法检查是否为 ELF 文件，映射了普通文件的区域都视为模块
+fn is_file_module(name: &str) -> bool {
+    if name == "[stack]" || name == "[heap]" {
+        return true;
+    }
+    name.starts_with('/') && !name.starts_with("/dev/") && !name.starts_with("/memfd:")
+}
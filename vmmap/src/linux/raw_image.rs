@@ -0,0 +1,163 @@
+use std::{
+    fs::{self, File},
+    io,
+    os::unix::prelude::FileExt,
+    path::{Path, PathBuf},
+};
+
+use super::{Error, Mapping, Pid, ProcessInfo, Result, VirtualMemoryRead};
+
+// 由原始内存镜像组成的客户机地址空间，例如从模拟器中导出的主机内存，
+// 地址和指针都使用客户机地址，一个镜像可以对应多个区域
+pub struct RawImage {
+    pub pid: Pid,
+    pub pathname: PathBuf,
+    images: Vec<File>,
+    regions: Vec<GuestRegion>,
+}
+
+// 客户机地址 [start, end) 对应 images[image] 中从 offset 开始的数据
+#[derive(Debug, Clone)]
+pub struct GuestRegion {
+    pub start: usize,
+    pub end: usize,
+    // 与 /proc/pid/maps 相同的权限，例如 rw-p
+    pub flags: String,
+    pub image: usize,
+    pub offset: u64,
+    pub name: Option<String>,
+}
+
+impl VirtualMemoryRead for RawImage {
+    fn read_at(&self, buf: &mut [u8], offset: usize) -> Result<usize> {
+        self._read_at(buf, offset).map_err(Error::ReadMemory)
+    }
+
+    fn read_exact_at(&self, buf: &mut [u8], offset: usize) -> Result<()> {
+        let mut done = 0;
+        while done < buf.len() {
+            done += self.read_at(&mut buf[done..], offset + done)?;
+        }
+        Ok(())
+    }
+}
+
+impl ProcessInfo for RawImage {
+    fn pid(&self) -> Pid {
+        self.pid
+    }
+
+    fn app_path(&self) -> &Path {
+        &self.pathname
+    }
+
+    fn get_maps(&self) -> impl Iterator<Item = Result<Mapping>> {
+        self.regions.iter().map(|r| {
+            Ok(Mapping {
+                start: r.start,
+                end: r.end,
+                flags: r.flags.clone(),
+                offset: r.offset as usize,
+                dev: String::from("00:00"),
+                inode: 0,
+                name: r.name.clone(),
+            })
+        })
+    }
+
+    // 区域由用户指定，全部可以作为模块
+    fn is_module(&self, _name: &str) -> Option<bool> {
+        Some(true)
+    }
+}
+
+impl RawImage {
+    // 检查区域是否重叠、是否超出镜像文件，pathname 只用于显示
+    pub fn new(pathname: PathBuf, images: Vec<File>, regions: Vec<GuestRegion>) -> Result<Self> {
+        Self::_new(pathname, images, regions).map_err(Error::OpenProcess)
+    }
+
+    // 读取区域表文件，每行一个区域，# 开头的行是注释
+    // 客户机起始-结束 权限 镜像文件 镜像内偏移 [名称]
+    // 80000000-80400000 rw-p main.bin 0 main
+    // 地址和偏移都是十六进制，镜像文件的相对路径相对于区域表所在的目录
+    pub fn open<P: AsRef<Path>>(table: P) -> Result<Self> {
+        Self::_open(table.as_ref()).map_err(Error::OpenProcess)
+    }
+
+    fn _open(table: &Path) -> Result<Self, io::Error> {
+        let contents = fs::read_to_string(table)?;
+        let dir = table.parent().unwrap_or(Path::new(""));
+        let mut paths = Vec::<PathBuf>::new();
+        let mut images = Vec::new();
+        let mut regions = Vec::new();
+        for (i, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid_line = || invalid(&format!("invalid region at line {}: {line}", i + 1));
+            let mut split = line.split_whitespace();
+            let (start, end) = split.next().and_then(|s| s.split_once('-')).ok_or_else(invalid_line)?;
+            let start = parse_hex(start).ok_or_else(invalid_line)?;
+            let end = parse_hex(end).ok_or_else(invalid_line)?;
+            let flags = split.next().filter(|s| s.len() == 4).ok_or_else(invalid_line)?;
+            let path = dir.join(split.next().ok_or_else(invalid_line)?);
+            let offset = split.next().and_then(parse_hex).ok_or_else(invalid_line)? as u64;
+            let name = split.next().map(String::from);
+            let image = match paths.iter().position(|p| *p == path) {
+                Some(idx) => idx,
+                None => {
+                    images.push(File::open(&path)?);
+                    paths.push(path);
+                    images.len() - 1
+                }
+            };
+            regions.push(GuestRegion { start, end, flags: flags.to_string(), image, offset, name });
+        }
+        Self::_new(table.to_path_buf(), images, regions)
+    }
+
+    fn _new(pathname: PathBuf, images: Vec<File>, regions: Vec<GuestRegion>) -> Result<Self, io::Error> {
+        let mut regions = regions;
+        regions.sort_unstable_by_key(|r| r.start);
+        for r in &regions {
+            let image = images
+                .get(r.image)
+                .ok_or_else(|| invalid("region image index out of range"))?;
+            let size = r.end.checked_sub(r.start).filter(|&n| n != 0);
+            let end = size.and_then(|n| r.offset.checked_add(n as u64));
+            if end.is_none_or(|end| end > image.metadata().map_or(0, |m| m.len())) {
+                return Err(invalid(&format!("region {:x}-{:x} is outside its image", r.start, r.end)));
+            }
+        }
+        if regions.windows(2).any(|w| w[0].end > w[1].start) {
+            return Err(invalid("regions overlap"));
+        }
+        Ok(Self { pid: 0, pathname, images, regions })
+    }
+
+    // 只读取 addr 所在的区域，跨越多个区域时由调用者继续读取
+    fn _read_at(&self, buf: &mut [u8], addr: usize) -> Result<usize, io::Error> {
+        let idx = self.regions.partition_point(|r| r.start <= addr);
+        let region = idx
+            .checked_sub(1)
+            .map(|i| &self.regions[i])
+            .filter(|r| addr < r.end)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "address is not in any guest region"))?;
+        let start = addr - region.start;
+        let size = buf.len().min(region.end - addr);
+        self.images[region.image].read_exact_at(&mut buf[..size], region.offset + start as u64)?;
+        Ok(size)
+    }
+}
+
+#[inline]
+fn parse_hex(s: &str) -> Option<usize> {
+    usize::from_str_radix(s.trim_start_matches("0x"), 16).ok()
+}
+
+#[inline]
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
@@ -0,0 +1,375 @@
+use std::{
+    ffi::OsString,
+    io::{self, BufReader, Read, Write},
+    net::TcpStream,
+    os::unix::{ffi::OsStringExt, net::UnixStream},
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+};
+
+use super::{
+    is_file_module, Error, Mapping, Pid, Process, ProcessInfo, Result, VirtualMemoryRead, VirtualMemoryWrite,
+    VirtualQuery, VirtualQueryExt,
+};
+
+// 远程内存代理协议，整数都是小端序，字符串和数据都是 u32 长度 + 字节
+// 请求是操作码 + 参数，响应是状态 + 内容，失败时内容是错误信息
+// OPEN  magic version pid:u32 -> app_path
+// MAPS  -> count:u32 + [start end flags offset dev inode name]*
+// READ  addr:u64 size:u32 -> data
+// WRITE addr:u64 data -> size:u32
+const MAGIC: &[u8; 4] = b"PTRX";
+const VERSION: u8 = 1;
+const OP_OPEN: u8 = 0;
+const OP_MAPS: u8 = 1;
+const OP_READ: u8 = 2;
+const OP_WRITE: u8 = 3;
+const STATUS_OK: u8 = 0;
+const STATUS_ERR: u8 = 1;
+
+// 单次读写的最大字节数，更大的请求由调用者分多次完成
+pub const MAX_TRANSFER: usize = 0x100000;
+// 响应的最大字节数，防止错误的数据导致分配过多内存
+const MAX_MESSAGE: usize = 0x4000000;
+
+// 通过代理访问另一台机器上的进程，地址是 host:port 或 unix:/path
+// 同一个连接上的请求依次执行
+pub struct RemoteProcess {
+    pub pid: Pid,
+    pub pathname: PathBuf,
+    conn: Mutex<BufReader<Stream>>,
+}
+
+enum Stream {
+    Tcp(TcpStream),
+    Unix(UnixStream),
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(s) => s.read(buf),
+            Stream::Unix(s) => s.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(s) => s.write(buf),
+            Stream::Unix(s) => s.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Tcp(s) => s.flush(),
+            Stream::Unix(s) => s.flush(),
+        }
+    }
+}
+
+impl VirtualMemoryRead for RemoteProcess {
+    fn read_at(&self, buf: &mut [u8], offset: usize) -> Result<usize> {
+        self._read_at(buf, offset).map_err(Error::ReadMemory)
+    }
+
+    fn read_exact_at(&self, buf: &mut [u8], offset: usize) -> Result<()> {
+        let mut done = 0;
+        while done < buf.len() {
+            match self.read_at(&mut buf[done..], offset + done)? {
+                0 => return Err(Error::ReadMemory(io::ErrorKind::UnexpectedEof.into())),
+                n => done += n,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl VirtualMemoryWrite for RemoteProcess {
+    fn write_at(&self, buf: &[u8], offset: usize) -> Result<usize> {
+        self._write_at(buf, offset).map_err(Error::WriteMemory)
+    }
+
+    fn write_all_at(&self, buf: &[u8], offset: usize) -> Result<()> {
+        let mut done = 0;
+        while done < buf.len() {
+            match self.write_at(&buf[done..], offset + done)? {
+                0 => return Err(Error::WriteMemory(io::ErrorKind::WriteZero.into())),
+                n => done += n,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ProcessInfo for RemoteProcess {
+    fn pid(&self) -> Pid {
+        self.pid
+    }
+
+    fn app_path(&self) -> &Path {
+        &self.pathname
+    }
+
+    fn get_maps(&self) -> impl Iterator<Item = Result<Mapping>> {
+        let (maps, err) = match self._get_maps() {
+            Ok(maps) => (maps, None),
+            Err(err) => (Vec::new(), Some(Error::QueryMapping(err))),
+        };
+        maps.into_iter().map(Ok).chain(err.map(Err))
+    }
+
+    // 文件在远程机器上，不读取本机的文件
+    fn is_module(&self, name: &str) -> Option<bool> {
+        Some(is_file_module(name))
+    }
+}
+
+impl RemoteProcess {
+    pub fn connect(addr: &str, pid: Pid) -> Result<Self> {
+        Self::_connect(addr, pid).map_err(Error::OpenProcess)
+    }
+
+    fn _connect(addr: &str, pid: Pid) -> Result<Self, io::Error> {
+        let stream = match addr.strip_prefix("unix:") {
+            Some(path) => Stream::Unix(UnixStream::connect(path)?),
+            None => {
+                let stream = TcpStream::connect(addr)?;
+                stream.set_nodelay(true)?;
+                Stream::Tcp(stream)
+            }
+        };
+        let mut conn = BufReader::new(stream);
+        let mut req = vec![OP_OPEN];
+        req.extend_from_slice(MAGIC);
+        req.push(VERSION);
+        req.extend_from_slice(&(pid as u32).to_le_bytes());
+        let path = request(&mut conn, &req)?;
+        let pathname = PathBuf::from(OsString::from_vec(path));
+        Ok(Self { pid, pathname, conn: Mutex::new(conn) })
+    }
+
+    fn request(&self, req: &[u8]) -> Result<Vec<u8>, io::Error> {
+        let mut conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        request(&mut conn, req)
+    }
+
+    fn _read_at(&self, buf: &mut [u8], addr: usize) -> Result<usize, io::Error> {
+        let size = buf.len().min(MAX_TRANSFER);
+        let mut req = vec![OP_READ];
+        req.extend_from_slice(&(addr as u64).to_le_bytes());
+        req.extend_from_slice(&(size as u32).to_le_bytes());
+        let data = self.request(&req)?;
+        if data.len() > size {
+            return Err(invalid("read response is too long"));
+        }
+        buf[..data.len()].copy_from_slice(&data);
+        Ok(data.len())
+    }
+
+    fn _write_at(&self, buf: &[u8], addr: usize) -> Result<usize, io::Error> {
+        let data = &buf[..buf.len().min(MAX_TRANSFER)];
+        let mut req = vec![OP_WRITE];
+        req.extend_from_slice(&(addr as u64).to_le_bytes());
+        put_bytes(&mut req, data);
+        let resp = self.request(&req)?;
+        Decoder(&resp).u32().map(|n| (n as usize).min(data.len()))
+    }
+
+    fn _get_maps(&self) -> Result<Vec<Mapping>, io::Error> {
+        let resp = self.request(&[OP_MAPS])?;
+        let mut de = Decoder(&resp);
+        (0..de.u32()?)
+            .map(|_| {
+                Ok(Mapping {
+                    start: de.u64()? as usize,
+                    end: de.u64()? as usize,
+                    flags: de.string()?,
+                    offset: de.u64()? as usize,
+                    dev: de.string()?,
+                    inode: de.u64()? as usize,
+                    name: match de.u8()? {
+                        0 => None,
+                        _ => Some(de.string()?),
+                    },
+                })
+            })
+            .collect()
+    }
+}
+
+// 处理一个客户端连接，第一个请求必须是 OPEN，客户端断开时返回
+// 读写失败等错误返回给客户端，协议错误时断开连接
+pub fn serve<S: Read + Write>(stream: S) -> Result<(), io::Error> {
+    let mut stream = BufReader::new(stream);
+    let mut proc = None::<Process>;
+    loop {
+        let mut op = [0];
+        if stream.read(&mut op)? == 0 {
+            return Ok(());
+        }
+        let result = match op[0] {
+            OP_OPEN if proc.is_none() => {
+                let mut magic = [0; 5];
+                stream.read_exact(&mut magic)?;
+                if magic[..4] != *MAGIC || magic[4] != VERSION {
+                    return Err(invalid("unsupported protocol version"));
+                }
+                let pid = read_u32(&mut stream)? as Pid;
+                Process::open(pid).map_err(io_error).map(|p| {
+                    let path = p.pathname.clone().into_os_string().into_vec();
+                    proc = Some(p);
+                    path
+                })
+            }
+            OP_MAPS => encode_maps(opened(&proc)?),
+            OP_READ => {
+                let addr = read_u64(&mut stream)? as usize;
+                let size = read_u32(&mut stream)? as usize;
+                if size > MAX_TRANSFER {
+                    return Err(invalid("read request is too large"));
+                }
+                let mut buf = vec![0; size];
+                opened(&proc)?.read_at(&mut buf, addr).map_err(io_error).map(|n| {
+                    buf.truncate(n);
+                    buf
+                })
+            }
+            OP_WRITE => {
+                let addr = read_u64(&mut stream)? as usize;
+                let size = read_u32(&mut stream)? as usize;
+                if size > MAX_TRANSFER {
+                    return Err(invalid("write request is too large"));
+                }
+                let mut buf = vec![0; size];
+                stream.read_exact(&mut buf)?;
+                opened(&proc)?
+                    .write_at(&buf, addr)
+                    .map_err(io_error)
+                    .map(|n| (n as u32).to_le_bytes().to_vec())
+            }
+            _ => return Err(invalid("unexpected request")),
+        };
+        let (status, payload) = match result {
+            Ok(payload) => (STATUS_OK, payload),
+            Err(err) => (STATUS_ERR, err.to_string().into_bytes()),
+        };
+        let mut resp = Vec::with_capacity(payload.len() + 5);
+        resp.push(status);
+        put_bytes(&mut resp, &payload);
+        let writer = stream.get_mut();
+        writer.write_all(&resp)?;
+        writer.flush()?;
+    }
+}
+
+fn opened(proc: &Option<Process>) -> Result<&Process, io::Error> {
+    proc.as_ref().ok_or_else(|| invalid("process is not opened"))
+}
+
+// 只把 io::Error 发送给客户端，客户端会重新包装为对应的 Error
+fn io_error(err: Error) -> io::Error {
+    match err {
+        Error::OpenProcess(err) | Error::ReadMemory(err) | Error::WriteMemory(err) | Error::QueryMapping(err) => err,
+    }
+}
+
+fn encode_maps(proc: &Process) -> Result<Vec<u8>, io::Error> {
+    let maps = proc.get_maps().collect::<Result<Vec<_>>>().map_err(io_error)?;
+    let mut buf = Vec::new();
+    buf.extend_from_slice(&(maps.len() as u32).to_le_bytes());
+    for m in &maps {
+        buf.extend_from_slice(&(m.start() as u64).to_le_bytes());
+        buf.extend_from_slice(&(m.end() as u64).to_le_bytes());
+        put_bytes(&mut buf, m.flags.as_bytes());
+        buf.extend_from_slice(&(m.offset() as u64).to_le_bytes());
+        put_bytes(&mut buf, m.dev().as_bytes());
+        buf.extend_from_slice(&(m.inode() as u64).to_le_bytes());
+        match m.name() {
+            Some(name) => {
+                buf.push(1);
+                put_bytes(&mut buf, name.as_bytes());
+            }
+            None => buf.push(0),
+        }
+    }
+    Ok(buf)
+}
+
+// 发送一个请求并读取响应内容，失败的响应转换为错误
+fn request<S: Read + Write>(conn: &mut BufReader<S>, req: &[u8]) -> Result<Vec<u8>, io::Error> {
+    let writer = conn.get_mut();
+    writer.write_all(req)?;
+    writer.flush()?;
+    let mut status = [0];
+    conn.read_exact(&mut status)?;
+    let size = read_u32(conn)? as usize;
+    if size > MAX_MESSAGE {
+        return Err(invalid("response is too large"));
+    }
+    let mut payload = vec![0; size];
+    conn.read_exact(&mut payload)?;
+    match status[0] {
+        STATUS_OK => Ok(payload),
+        STATUS_ERR => Err(io::Error::other(String::from_utf8_lossy(&payload))),
+        _ => Err(invalid("invalid response status")),
+    }
+}
+
+// 按顺序解析响应内容
+struct Decoder<'a>(&'a [u8]);
+
+impl Decoder<'_> {
+    fn take(&mut self, n: usize) -> Result<&[u8], io::Error> {
+        if self.0.len() < n {
+            return Err(invalid("response is truncated"));
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, io::Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, io::Error> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, io::Error> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> Result<String, io::Error> {
+        let size = self.u32()? as usize;
+        Ok(String::from_utf8_lossy(self.take(size)?).into_owned())
+    }
+}
+
+#[inline]
+fn put_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    buf.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    buf.extend_from_slice(bytes);
+}
+
+#[inline]
+fn read_u32<R: Read>(r: &mut R) -> Result<u32, io::Error> {
+    let mut buf = [0; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+#[inline]
+fn read_u64<R: Read>(r: &mut R) -> Result<u64, io::Error> {
+    let mut buf = [0; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+#[inline]
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
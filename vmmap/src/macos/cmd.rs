@@ -6,6 +6,9 @@ pub trait ProcessInfoCmdFixed {
     fn pid(&self) -> Pid;
     fn app_path(&self) -> &std::path::Path;
     fn get_maps(&self) -> impl Iterator<Item = crate::Result<Mapping>>;
+    fn is_module(&self, _name: &str) -> Option<bool> {
+        None
+    }
 }
 
 pub struct Mapping {